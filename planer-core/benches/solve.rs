@@ -0,0 +1,70 @@
+use chrono::{prelude::*, Duration};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use planer_core::{PlanerData, planer::{calendar::Event, BlockedTime}, solver};
+
+const EXAMS: usize = 300;
+const ROOMS: usize = 20;
+const TEACHERS: usize = 40;
+const STUDENTS: usize = 600;
+/// days of imported appointments before the solved day, they fill the calendars like a school term would
+const TERM_DAYS: i64 = 60;
+
+fn day() -> Date<Utc> { Utc.ymd(2022, 12, 15) }
+
+fn blocked(start: DateTime<Utc>, i: usize) -> Event<BlockedTime> {
+    let summary = format!("lesson {i}");
+    Event::new(start, Duration::minutes(45), BlockedTime { uid: summary.clone(), summary })
+}
+
+fn plan() -> PlanerData {
+    let mut data = PlanerData::default();
+
+    let rooms: Vec<_> = (0..ROOMS).map(|i| data.add_room(format!("A{i:02}"), Vec::new())).collect();
+    let teachers: Vec<_> = (0..TEACHERS).map(|i| data.add_teacher(format!("T{i}"), format!("Teacher{i}"), None, Some(format!("T{i:02}")), &[])).collect();
+    let students: Vec<_> = (0..STUDENTS).map(|i| data.add_student(format!("S{i}"), format!("Student{i}"), None)).collect();
+
+    let term_start = day().and_hms(8, 0, 0) - Duration::days(TERM_DAYS);
+    for d in 0..TERM_DAYS {
+        for lesson in 0..6 {
+            let start = term_start + Duration::days(d) + Duration::minutes(lesson * 50);
+            for (i, room) in rooms.iter().enumerate() {
+                data.rooms[*room].blocked.add_event(blocked(start, i));
+            }
+            for (i, teacher) in teachers.iter().enumerate() {
+                data.teachers[*teacher].blocked.add_event(blocked(start, i));
+            }
+        }
+    }
+
+    for i in 0..EXAMS {
+        let exam = data.add_exam(format!("exam-{i}"), Duration::minutes(30), vec!["Eng".to_owned()], Vec::new());
+        let exam = &mut data.unfinished_exams[exam];
+        exam.examiners = [Some(teachers[i % TEACHERS]), Some(teachers[(i * 7 + 1) % TEACHERS]), None];
+        exam.examinees = vec![students[(i * 2) % STUDENTS], students[(i * 2 + 1) % STUDENTS]];
+    }
+
+    data
+}
+
+/// compares a single thread with all available ones
+fn solve_plan(c: &mut Criterion) {
+    let mut threads = vec![1, std::thread::available_parallelism().map_or(1, |v| v.get())];
+    threads.dedup();
+
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    for threads in threads {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        group.bench_function(format!("{EXAMS} exams, {ROOMS} rooms, {threads} threads"), |b| {
+            b.iter_batched(plan, |mut data| {
+                let _ = pool.install(|| solver::solve(&mut data, day()));
+                data
+            }, BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solve_plan);
+criterion_main!(benches);
@@ -0,0 +1,113 @@
+pub mod html;
+pub mod ics;
+pub mod pdf;
+pub mod sheet;
+
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use uuid::Uuid;
+
+use crate::planer::{PlanerData, Name, Room, store::{Id, AsUuid}};
+
+/// turns a name into something that can safely be used as a file name
+pub fn file_name(name: &str) -> String {
+    let name: String = name.trim().chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+    .collect();
+
+    if name.is_empty() { "unnamed".to_owned() } else { name }
+}
+
+/// a participant of a booked exam
+#[derive(Debug, Clone)]
+pub struct Participant {
+    pub uuid: Uuid,
+    pub name: Name,
+    /// only set for teachers
+    pub shorthand: Option<String>,
+}
+
+/// a booked exam with its room, people and end time already resolved, so every export formats the same values
+#[derive(Debug, Clone)]
+pub struct Booking {
+    pub uuid: Uuid,
+    pub id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub room_id: Id<Room>,
+    /// the number of the room
+    pub room: String,
+    pub subjects: Vec<String>,
+    pub examiners: Vec<Participant>,
+    pub examinees: Vec<Participant>,
+    pub tags: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl Booking {
+    pub fn id(&self) -> &str {
+        if self.id.is_empty() { "[unnamed]" } else { &self.id[..] }
+    }
+
+    pub fn examiner_shorthands(&self) -> String {
+        self.examiners.iter().filter_map(|v| v.shorthand.clone()).collect::<Vec<_>>().join(", ")
+    }
+
+    pub fn examinee_names(&self) -> String {
+        self.examinees.iter().map(|v| format!("{}", v.name)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+impl PlanerData {
+    /// the name of the plan (the file name without extension)
+    pub fn plan_name(&self) -> String {
+        self.current_file_name.as_ref()
+            .and_then(|v| std::path::Path::new(v).file_stem())
+            .map(|v| v.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unnamed".to_owned())
+    }
+
+    /// all finished exams that have a room and a time, sorted by start, room and id
+    pub fn bookings(&self) -> Vec<Booking> {
+        let mut bookings: Vec<_> = self.finished_exams.iter()
+            .filter_map(|exam| {
+                let (room_id, start) = exam.pairing.as_ref()?;
+                let room = self.rooms.get(*room_id).map(|v| v.number.clone()).unwrap_or_default();
+
+                Some(Booking {
+                    uuid: exam.uuid,
+                    id: exam.id.clone(),
+                    start: *start,
+                    end: *start + exam.duration,
+                    room_id: *room_id,
+                    room,
+                    subjects: exam.subjects.clone(),
+                    examiners: exam.examiners.iter()
+                        .filter_map(|v| self.teachers.get((*v)?))
+                        .map(|v| Participant { uuid: v.as_uuid(), name: v.name.clone(), shorthand: Some(v.shorthand.clone()) })
+                    .collect(),
+                    examinees: exam.examinees.iter()
+                        .filter_map(|v| self.students.get(*v))
+                        .map(|v| Participant { uuid: v.as_uuid(), name: v.name.clone(), shorthand: None })
+                    .collect(),
+                    tags: exam.tags.iter().map(|v| v.name.clone()).collect(),
+                    error: exam.error_message(),
+                })
+            })
+        .collect();
+
+        bookings.sort_by(|a, b| (a.start, &a.room, &a.id, a.uuid).cmp(&(b.start, &b.room, &b.id, b.uuid)));
+        bookings
+    }
+}
+
+/// groups sorted bookings by their day
+pub fn by_day<'a>(bookings: impl IntoIterator<Item = &'a Booking>) -> BTreeMap<NaiveDate, Vec<&'a Booking>> {
+    let mut days: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for booking in bookings {
+        days.entry(booking.start.naive_utc().date()).or_default().push(booking);
+    }
+
+    days
+}
@@ -0,0 +1,239 @@
+use std::{path::Path, collections::{BTreeMap, HashSet}};
+
+use uuid::Uuid;
+
+use crate::planer::{PlanerData, store::AsUuid};
+
+use super::{Booking, Participant, by_day, file_name};
+
+/// how students are shown in the published pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StudentLabel {
+    /// `J. B.`
+    #[default]
+    Initials,
+    /// the first block of the uuid
+    Id,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HtmlOptions {
+    pub students: StudentLabel,
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+nav { margin-bottom: 1em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+abbr { text-decoration: none; border-bottom: 1px dotted #888; cursor: help; }
+.conflict { background: #fbdada; }
+.weak { color: #888; }
+";
+
+/// a page of the bundle before it is rendered
+struct Page {
+    file: String,
+    title: String,
+}
+
+impl PlanerData {
+    /// writes a self-contained html bundle into `dir` and returns the number of written files
+    pub fn export_html(&self, dir: impl AsRef<Path>, options: &HtmlOptions) -> std::io::Result<usize> {
+        let files = self.render_html(options);
+        for (name, content) in &files {
+            std::fs::write(dir.as_ref().join(name), content)?;
+        }
+
+        Ok(files.len())
+    }
+
+    /// renders all pages of the bundle (file name -> content).
+    /// the output only depends on the plan, so diffs between revisions stay meaningful
+    pub fn render_html(&self, options: &HtmlOptions) -> BTreeMap<String, String> {
+        let bookings = self.bookings();
+        let days = by_day(&bookings);
+        let plan_name = self.plan_name();
+
+        let mut rooms: Vec<_> = self.rooms.iter().map(|v| v.number.clone()).collect();
+        rooms.sort();
+        rooms.dedup();
+
+        let mut teachers: Vec<_> = self.teachers.iter()
+            .map(|v| (v.shorthand.clone(), v.as_uuid(), format!("{}", v.name)))
+        .collect();
+        teachers.sort();
+
+        let mut used = HashSet::new();
+        let day_pages: Vec<_> = days.keys().map(|day| Page {
+            file: unique_file(&mut used, &format!("day-{}", day.format("%Y-%m-%d"))),
+            title: day.format("%A %d.%m.%Y").to_string(),
+        }).collect();
+        let room_pages: Vec<_> = rooms.iter().map(|room| Page {
+            file: unique_file(&mut used, &format!("room-{}", file_name(room))),
+            title: format!("room {room}"),
+        }).collect();
+        let teacher_pages: Vec<_> = teachers.iter().map(|(shorthand, _, name)| Page {
+            file: unique_file(&mut used, &format!("teacher-{}", file_name(shorthand))),
+            title: format!("{name} ({shorthand})"),
+        }).collect();
+
+        let room_files: BTreeMap<_, _> = rooms.iter().zip(&room_pages).map(|(room, page)| (&room[..], &page.file[..])).collect();
+        let teacher_files: BTreeMap<_, _> = teachers.iter().zip(&teacher_pages).map(|((_, uuid, _), page)| (*uuid, &page.file[..])).collect();
+        let links = Links { rooms: room_files, teachers: teacher_files, options };
+
+        let mut files = BTreeMap::new();
+        files.insert("style.css".to_owned(), STYLE.to_owned());
+
+        let mut index = String::new();
+        for (heading, pages) in [("days", &day_pages), ("rooms", &room_pages), ("teachers", &teacher_pages)] {
+            index.push_str(&format!("<h2>{heading}</h2>\n<ul>\n"));
+            for page in pages.iter() {
+                index.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", escape(&page.file), escape(&page.title)));
+            }
+            if pages.is_empty() { index.push_str("<li class=\"weak\">none</li>\n") }
+            index.push_str("</ul>\n");
+        }
+        files.insert("index.html".to_owned(), document(&plan_name, &plan_name, &index));
+
+        for (page, day_bookings) in day_pages.iter().zip(days.values()) {
+            let body = links.table(day_bookings.iter().copied(), true);
+            files.insert(page.file.clone(), document(&plan_name, &page.title, &body));
+        }
+
+        for (page, room) in room_pages.iter().zip(&rooms) {
+            let body = links.tables_by_day(bookings.iter().filter(|v| v.room == *room), false);
+            files.insert(page.file.clone(), document(&plan_name, &page.title, &body));
+        }
+
+        for (page, (_, uuid, _)) in teacher_pages.iter().zip(&teachers) {
+            let body = links.tables_by_day(bookings.iter().filter(|v| v.examiners.iter().any(|v| v.uuid == *uuid)), true);
+            files.insert(page.file.clone(), document(&plan_name, &page.title, &body));
+        }
+
+        files
+    }
+}
+
+struct Links<'a> {
+    rooms: BTreeMap<&'a str, &'a str>,
+    teachers: BTreeMap<Uuid, &'a str>,
+    options: &'a HtmlOptions,
+}
+
+impl<'a> Links<'a> {
+    fn tables_by_day<'b>(&self, bookings: impl Iterator<Item = &'b Booking>, show_room: bool) -> String {
+        let days = by_day(bookings);
+        if days.is_empty() { return "<p class=\"weak\">no exams</p>\n".to_owned() }
+
+        days.iter().map(|(day, bookings)| {
+            format!("<h2>{}</h2>\n{}", day.format("%A %d.%m.%Y"), self.table(bookings.iter().copied(), show_room))
+        }).collect()
+    }
+
+    fn table<'b>(&self, bookings: impl Iterator<Item = &'b Booking>, show_room: bool) -> String {
+        let mut res = String::from("<table>\n<tr><th>time</th>");
+        if show_room { res.push_str("<th>room</th>") }
+        res.push_str("<th>exam</th><th>duration</th><th>subjects</th><th>examiners</th><th>examinees</th></tr>\n");
+
+        for booking in bookings {
+            let class = if booking.error.is_some() { " class=\"conflict\"" } else { "" };
+            res.push_str(&format!("<tr{class}><td>{}-{}</td>", booking.start.format("%H:%M"), booking.end.format("%H:%M")));
+            if show_room {
+                let room = match self.rooms.get(&booking.room[..]) {
+                    Some(file) => format!("<a href=\"{}\">{}</a>", escape(file), escape(&booking.room)),
+                    None => escape(&booking.room),
+                };
+                res.push_str(&format!("<td>{room}</td>"));
+            }
+
+            let examiners = booking.examiners.iter().map(|v| self.examiner(v)).collect::<Vec<_>>().join(", ");
+            let examinees = booking.examinees.iter().map(|v| escape(&self.student(v))).collect::<Vec<_>>().join(", ");
+            res.push_str(&format!(
+                "<td>{}</td><td>{}min</td><td>{}</td><td>{examiners}</td><td>{examinees}</td></tr>\n",
+                escape(booking.id()),
+                (booking.end - booking.start).num_minutes(),
+                escape(&booking.subjects.join(", ")),
+            ));
+        }
+
+        res.push_str("</table>\n");
+        res
+    }
+
+    /// the shorthand with the full name on hover
+    fn examiner(&self, examiner: &Participant) -> String {
+        let shorthand = escape(examiner.shorthand.as_deref().unwrap_or(""));
+        let abbr = format!("<abbr title=\"{}\">{shorthand}</abbr>", escape(&format!("{}", examiner.name)));
+        match self.teachers.get(&examiner.uuid) {
+            Some(file) => format!("<a href=\"{}\">{abbr}</a>", escape(file)),
+            None => abbr,
+        }
+    }
+
+    fn student(&self, student: &Participant) -> String {
+        match self.options.students {
+            StudentLabel::Initials => {
+                let initial = |v: &str| v.chars().next().map(|c| format!("{c}.")).unwrap_or_default();
+                format!("{} {}", initial(&student.name.first), initial(&student.name.last))
+            },
+            StudentLabel::Id => student.uuid.to_string()[..8].to_owned(),
+        }
+    }
+}
+
+fn unique_file(used: &mut HashSet<String>, name: &str) -> String {
+    let mut file = format!("{name}.html");
+    let mut n = 1;
+    while !used.insert(file.clone()) {
+        n += 1;
+        file = format!("{name}-{n}.html");
+    }
+
+    file
+}
+
+fn document(plan_name: &str, title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} - {}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n<nav><a href=\"index.html\">{}</a></nav>\n<h1>{}</h1>\n{body}</body>\n</html>\n",
+        escape(plan_name), escape(title), escape(plan_name), escape(title),
+    )
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use super::*;
+
+    #[test]
+    fn render_html_bundle() {
+        let mut data = PlanerData::default();
+        let teacher = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let student = data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let exam = data.add_exam("<eng-1>".to_owned(), Duration::minutes(30), vec!["Eng".to_owned()], Vec::new());
+        data.unfinished_exams[exam].examiners[0] = Some(teacher);
+        data.unfinished_exams[exam].examinees.push(student);
+        data.book_exam(exam, room, Utc.ymd(2022, 12, 15).and_hms(8, 0, 0));
+        data.finish_exam(exam);
+
+        let files = data.render_html(&HtmlOptions::default());
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["day-2022-12-15.html", "index.html", "room-A1.03.html", "style.css", "teacher-Ee.html"]);
+        assert_eq!(files, data.render_html(&HtmlOptions::default()), "the output should be deterministic");
+
+        let day = &files["day-2022-12-15.html"];
+        assert!(day.contains("&lt;eng-1&gt;"), "the exam id should be escaped");
+        assert!(day.contains("<abbr title=\"Falk Eertel\">Ee</abbr>"));
+        assert!(day.contains("J. B.") && !day.contains("Jonas"), "students should be anonymized");
+    }
+}
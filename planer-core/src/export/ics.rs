@@ -0,0 +1,182 @@
+use std::{path::Path, collections::HashSet};
+
+use chrono::prelude::*;
+use uuid::Uuid;
+
+use crate::planer::{PlanerData, Exam, Teacher, Student, Room, calendar::Calendar, store::{Id, AsUuid}};
+
+use super::file_name;
+
+/// whose calendar is exported, this decides which participants are listed in the events
+enum Owner {
+    Teacher(Uuid),
+    Student,
+    Room,
+}
+
+/// times in the plan are wall clock times, so they are written as floating times
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+pub fn teacher_ics(data: &PlanerData, teacher: &Teacher) -> String {
+    let name = format!("{} ({})", teacher.name, teacher.shorthand);
+    calendar_ics(data, &name, &teacher.calendar, Owner::Teacher(teacher.as_uuid()))
+}
+
+pub fn student_ics(data: &PlanerData, student: &Student) -> String {
+    calendar_ics(data, &format!("{}", student.name), &student.calendar, Owner::Student)
+}
+
+pub fn room_ics(data: &PlanerData, room: &Room) -> String {
+    calendar_ics(data, &format!("room {}", room.number), &room.calendar, Owner::Room)
+}
+
+impl PlanerData {
+    /// writes one `.ics` file per teacher, student and room into `dir`
+    /// and returns the number of written files
+    pub fn export_ics_dir(&self, dir: impl AsRef<Path>) -> std::io::Result<usize> {
+        let dir = dir.as_ref();
+        let mut used_names = HashSet::new();
+        let mut write = |name: String, content: String| {
+            // two people can have the same name, so the later ones get a number
+            let mut file = format!("{name}.ics");
+            let mut n = 1;
+            while !used_names.insert(file.clone()) {
+                n += 1;
+                file = format!("{name}-{n}.ics");
+            }
+            std::fs::write(dir.join(file), content)
+        };
+
+        for teacher in &self.teachers {
+            write(format!("teacher-{}", file_name(&teacher.shorthand)), teacher_ics(self, teacher))?;
+        }
+
+        for student in &self.students {
+            write(format!("student-{}", file_name(&format!("{}_{}", student.name.last, student.name.first))), student_ics(self, student))?;
+        }
+
+        for room in &self.rooms {
+            write(format!("room-{}", file_name(&room.number)), room_ics(self, room))?;
+        }
+
+        Ok(used_names.len())
+    }
+}
+
+fn calendar_ics(data: &PlanerData, name: &str, calendar: &Calendar<Id<Exam>>, owner: Owner) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//planer//exam schedule//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        format!("X-WR-CALNAME:{}", escape(name)),
+    ];
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    for ev in calendar.events() {
+        let exam = match data.exam(ev.data) { Some(v) => v, None => continue };
+        let room = exam.pairing.as_ref().and_then(|(room, _)| data.rooms.get(*room)).map(|room| &room.number);
+
+        let id = if exam.id.is_empty() { "[unnamed]" } else { &exam.id[..] };
+        let summary = if exam.subjects.is_empty() { format!("exam {id}") }
+                      else { format!("exam {id} ({})", exam.subjects.join(", ")) };
+
+        let mut description = Vec::new();
+        if !exam.subjects.is_empty() { description.push(format!("subjects: {}", exam.subjects.join(", "))) }
+
+        let examiners: Vec<_> = exam.examiners.iter()
+            .filter_map(|v| data.teachers.get((*v)?))
+            // the owner is not a co-examiner
+            .filter(|v| !matches!(owner, Owner::Teacher(uuid) if v.as_uuid() == uuid))
+            .map(|v| format!("{} ({})", v.name, v.shorthand))
+        .collect();
+        if !examiners.is_empty() {
+            let label = if matches!(owner, Owner::Teacher(_)) { "co-examiners" } else { "examiners" };
+            description.push(format!("{label}: {}", examiners.join(", ")));
+        }
+
+        if !matches!(owner, Owner::Student) {
+            let examinees: Vec<_> = exam.examinees.iter()
+                .filter_map(|v| data.students.get(*v))
+                .map(|v| format!("{}", v.name))
+            .collect();
+            if !examinees.is_empty() { description.push(format!("examinees: {}", examinees.join(", "))) }
+        }
+
+        lines.push("BEGIN:VEVENT".to_owned());
+        // the uid only depends on the exam, so calendar apps update the event on re-import
+        lines.push(format!("UID:{}@planer", exam.uuid));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("DTSTART:{}", ev.start.format(TIME_FORMAT)));
+        lines.push(format!("DTEND:{}", (ev.start + ev.duration).format(TIME_FORMAT)));
+        lines.push(format!("SUMMARY:{}", escape(&summary)));
+        if let Some(room) = room { lines.push(format!("LOCATION:{}", escape(&format!("room {room}")))) }
+        if !description.is_empty() { lines.push(format!("DESCRIPTION:{}", escape(&description.join("\n")))) }
+        lines.push("END:VEVENT".to_owned());
+    }
+
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines.iter().map(|v| fold(v)).collect::<Vec<_>>().join("")
+}
+
+/// escapes a text value (RFC 5545 3.3.11)
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// folds a content line after at most 75 octets and terminates it with CRLF (RFC 5545 3.1)
+fn fold(line: &str) -> String {
+    let mut res = String::with_capacity(line.len() + 2);
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            res.push_str("\r\n ");
+            // the leading space counts towards the next line
+            len = 1;
+        }
+        res.push(c);
+        len += c.len_utf8();
+    }
+    res.push_str("\r\n");
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn export_teacher_ics() {
+        let mut data = PlanerData::default();
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let amanda = data.add_teacher("Amanda".to_owned(), "Adjei".to_owned(), None, Some("Adj".to_owned()), &[]);
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let exam = data.add_exam("eng-1".to_owned(), Duration::minutes(30), vec!["Eng".to_owned()], Vec::new());
+        data.unfinished_exams[exam].examiners = [Some(falk), Some(amanda), None];
+
+        let start = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+        data.book_exam(exam, room, start);
+        data.finish_exam(exam);
+
+        let ics = teacher_ics(&data, &data.teachers[falk]);
+        let uuid = exam.uuid();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains(&format!("UID:{uuid}@planer\r\n")), "the uid should be derived from the exam uuid");
+        assert!(ics.contains("DTSTART:20221215T080000\r\n"));
+        assert!(ics.contains("DTEND:20221215T083000\r\n"));
+        assert!(ics.contains("LOCATION:room A1.03\r\n"));
+        assert!(ics.contains("co-examiners: Amanda Adjei (Adj)"));
+        assert!(ics.lines().all(|v| v.len() <= 75), "all lines should be folded");
+
+        let ics = room_ics(&data, &data.rooms[room]);
+        assert!(ics.contains(&format!("UID:{uuid}@planer\r\n")));
+    }
+}
@@ -0,0 +1,463 @@
+use std::path::Path;
+
+use chrono::prelude::*;
+use printpdf::{PdfDocument, PdfDocumentReference, PdfLayerReference, IndirectFontRef, BuiltinFont, Mm, Point, Line, Rect, Color, Rgb, path::PaintMode};
+
+use crate::planer::{PlanerData, LessonType, store::AsUuid};
+
+use super::{Booking, by_day};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfLayout {
+    /// rooms by time like the calendar tab, one page per day
+    RoomGrid,
+    /// one page per teacher with all of their exams
+    Teachers,
+    /// one notification letter per student
+    Students,
+    /// all exams of a day as a list, one page per day
+    Days,
+}
+
+impl PdfLayout {
+    pub const ALL: [PdfLayout; 4] = [PdfLayout::RoomGrid, PdfLayout::Teachers, PdfLayout::Students, PdfLayout::Days];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PdfLayout::RoomGrid => "room grid",
+            PdfLayout::Teachers => "teacher lists",
+            PdfLayout::Students => "student letters",
+            PdfLayout::Days => "day overview",
+        }
+    }
+}
+
+impl PlanerData {
+    pub fn export_pdf(&self, layout: PdfLayout, path: impl AsRef<Path>) -> Result<(), String> {
+        let bytes = self.render_pdf(layout)?;
+        std::fs::write(path, bytes).map_err(|err| format!("could not write file: {err}"))
+    }
+
+    pub fn render_pdf(&self, layout: PdfLayout) -> Result<Vec<u8>, String> {
+        let bookings = self.bookings();
+        let mut writer = Writer::new(&self.plan_name(), layout == PdfLayout::RoomGrid)?;
+
+        match layout {
+            PdfLayout::RoomGrid => self.room_grid_pdf(&mut writer, &bookings),
+            PdfLayout::Teachers => self.teachers_pdf(&mut writer, &bookings),
+            PdfLayout::Students => self.students_pdf(&mut writer, &bookings),
+            PdfLayout::Days => days_pdf(&mut writer, &bookings),
+        }
+
+        writer.finish()
+    }
+
+    fn room_grid_pdf(&self, w: &mut Writer, bookings: &[Booking]) {
+        // rooms can share a number, so the columns are found by id
+        let rooms: Vec<_> = self.rooms.iter().map(|v| (v.id(), v.number.clone())).collect();
+        let times = &self.timetable.times;
+        if rooms.is_empty() || times.is_empty() {
+            w.page("room grid");
+            w.paragraph("the plan has no rooms or lessons");
+            return;
+        }
+
+        let start_t = times[0].start;
+        let last_lesson = times.last().unwrap();
+        let total_minutes = (last_lesson.start + last_lesson.duration).signed_duration_since(start_t).num_minutes().max(1) as f32;
+
+        let mut days = by_day(bookings);
+        if days.is_empty() { days.insert(Utc::now().naive_utc().date(), Vec::new()); }
+
+        let time_width = 14.0;
+        let max_rooms = (((w.width - 2.0 * MARGIN - time_width) / 32.0) as usize).max(1);
+        for (day, day_bookings) in days {
+            for (chunk_idx, chunk) in rooms.chunks(max_rooms).enumerate() {
+                let mut heading = day_heading(day);
+                if rooms.len() > max_rooms {
+                    let first = chunk_idx * max_rooms + 1;
+                    heading = format!("{heading}, rooms {first}-{}", first + chunk.len() - 1);
+                }
+                w.page(&heading);
+
+                let col_width = (w.width - 2.0 * MARGIN - time_width) / chunk.len() as f32;
+                let left = MARGIN + time_width;
+                for (i, (_, number)) in chunk.iter().enumerate() {
+                    w.text(left + i as f32 * col_width + 1.0, w.y, 10.0, true, &fit(number, col_width - 2.0, 10.0));
+                }
+
+                let top = w.y + 7.0;
+                let minute = (w.height - MARGIN - 4.0 - top) / total_minutes;
+                for lesson in times {
+                    let y = top + lesson.start.signed_duration_since(start_t).num_minutes() as f32 * minute;
+                    let height = lesson.duration.num_minutes() as f32 * minute;
+
+                    if matches!(lesson.lesson_type, LessonType::Break) {
+                        w.rect(MARGIN, y, w.width - 2.0 * MARGIN, height, Some(BREAK_COLOR), false);
+                    }
+                    w.text(MARGIN, y + 0.5, 7.0, false, &lesson.start.format("%H:%M").to_string());
+                    w.hline(y, MARGIN, w.width - MARGIN);
+                }
+                w.hline(top + total_minutes * minute, MARGIN, w.width - MARGIN);
+                for i in 0..=chunk.len() {
+                    let x = left + i as f32 * col_width;
+                    w.vline(x, top, top + total_minutes * minute);
+                }
+
+                for booking in &day_bookings {
+                    let i = match chunk.iter().position(|(id, _)| *id == booking.room_id) { Some(i) => i, None => continue };
+                    let from = (booking.start.time().signed_duration_since(start_t).num_minutes() as f32).max(0.0);
+                    let to = (booking.end.time().signed_duration_since(start_t).num_minutes() as f32).min(total_minutes);
+                    if to <= from { continue }
+
+                    let (x, y) = (left + i as f32 * col_width + 0.6, top + from * minute + 0.3);
+                    let (width, height) = (col_width - 1.2, (to - from) * minute - 0.6);
+                    let color = if booking.error.is_some() { ERROR_COLOR } else { EXAM_COLOR };
+                    w.rect(x, y, width, height, Some(color), true);
+
+                    let lines = [
+                        (true, booking.id().to_owned()),
+                        (false, booking.subjects.join(", ")),
+                        (false, booking.examiner_shorthands()),
+                    ];
+                    for (j, (bold, text)) in lines.iter().enumerate() {
+                        let line_y = y + 0.8 + j as f32 * 3.2;
+                        if line_y + 3.0 > y + height { break }
+                        w.text(x + 0.8, line_y, 7.0, *bold, &fit(text, width - 1.6, 7.0));
+                    }
+                }
+            }
+        }
+    }
+
+    fn teachers_pdf(&self, w: &mut Writer, bookings: &[Booking]) {
+        let widths = [22.0, 16.0, 28.0, 34.0, 30.0, 56.0];
+        let header = ["time", "room", "exam", "subjects", "co-examiners", "examinees"];
+
+        if self.teachers.is_empty() { w.page("teachers") }
+        for teacher in &self.teachers {
+            let uuid = teacher.as_uuid();
+            w.page(&format!("{} ({})", teacher.name, teacher.shorthand));
+
+            let own: Vec<_> = bookings.iter().filter(|v| v.examiners.iter().any(|v| v.uuid == uuid)).collect();
+            if own.is_empty() { w.paragraph("no exams") }
+
+            for (day, day_bookings) in by_day(own) {
+                w.subheading(&day_heading(day));
+                w.row(&widths, &header, true);
+                for booking in day_bookings {
+                    let co_examiners = booking.examiners.iter()
+                        .filter(|v| v.uuid != uuid)
+                        .filter_map(|v| v.shorthand.clone())
+                    .collect::<Vec<_>>().join(", ");
+
+                    w.table_row(&widths, &header, &[
+                        &time_range(booking),
+                        &booking.room,
+                        booking.id(),
+                        &booking.subjects.join(", "),
+                        &co_examiners,
+                        &booking.examinee_names(),
+                    ]);
+                }
+            }
+        }
+    }
+
+    fn students_pdf(&self, w: &mut Writer, bookings: &[Booking]) {
+        let widths = [36.0, 22.0, 16.0, 28.0, 34.0, 50.0];
+        let header = ["day", "time", "room", "exam", "subjects", "examiners"];
+
+        if self.students.is_empty() { w.page("students") }
+        for student in &self.students {
+            let (uuid, name) = (student.as_uuid(), &student.name);
+            w.page(&format!("{name}"));
+
+            let own: Vec<_> = bookings.iter().filter(|v| v.examinees.iter().any(|v| v.uuid == uuid)).collect();
+            w.paragraph(&format!("Dear {} {},", name.first, name.last));
+            if own.is_empty() {
+                w.paragraph("you are currently not scheduled for any exams.");
+                continue;
+            }
+
+            w.paragraph("you are scheduled for the following exams:");
+            w.row(&widths, &header, true);
+            for booking in own {
+                let examiners = booking.examiners.iter().map(|v| format!("{}", v.name)).collect::<Vec<_>>().join(", ");
+                w.table_row(&widths, &header, &[
+                    &booking.start.format("%a %d.%m.%Y").to_string(),
+                    &time_range(booking),
+                    &booking.room,
+                    booking.id(),
+                    &booking.subjects.join(", "),
+                    &examiners,
+                ]);
+            }
+            w.y += LINE_HEIGHT;
+            w.paragraph("please be in front of the room in time and bring this letter with you.");
+        }
+    }
+}
+
+fn days_pdf(w: &mut Writer, bookings: &[Booking]) {
+    let widths = [22.0, 16.0, 28.0, 36.0, 30.0, 54.0];
+    let header = ["time", "room", "exam", "subjects", "examiners", "examinees"];
+
+    let days = by_day(bookings);
+    if days.is_empty() {
+        w.page("day overview");
+        w.paragraph("no exams are scheduled");
+    }
+
+    for (day, day_bookings) in days {
+        w.page(&day_heading(day));
+        w.paragraph(&format!("{} exams", day_bookings.len()));
+        w.row(&widths, &header, true);
+        for booking in day_bookings {
+            w.table_row(&widths, &header, &[
+                &time_range(booking),
+                &booking.room,
+                booking.id(),
+                &booking.subjects.join(", "),
+                &booking.examiner_shorthands(),
+                &booking.examinee_names(),
+            ]);
+        }
+    }
+}
+
+fn day_heading(day: NaiveDate) -> String {
+    day.format("%A %d.%m.%Y").to_string()
+}
+
+fn time_range(booking: &Booking) -> String {
+    format!("{}-{}", booking.start.format("%H:%M"), booking.end.format("%H:%M"))
+}
+
+const MARGIN: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 20.0;
+const LINE_HEIGHT: f32 = 5.0;
+const PT_TO_MM: f32 = 0.3528;
+/// the average width of a character in helvetica relative to the font size
+const CHAR_WIDTH: f32 = 0.52;
+
+const BREAK_COLOR: (f32, f32, f32) = (0.92, 0.92, 0.92);
+const EXAM_COLOR: (f32, f32, f32) = (0.85, 0.91, 0.98);
+const ERROR_COLOR: (f32, f32, f32) = (0.98, 0.84, 0.84);
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * PT_TO_MM * CHAR_WIDTH
+}
+
+/// shortens the text so it fits into `width` (in mm)
+fn fit(text: &str, width: f32, size: f32) -> String {
+    if text_width(text, size) <= width { return text.to_owned() }
+
+    let max_chars = (width / (size * PT_TO_MM * CHAR_WIDTH)) as usize;
+    let mut res: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    res.push('…');
+    res
+}
+
+/// draws pages with a header and keeps track of the vertical position.
+/// positions are in mm from the top left corner of the page
+struct Writer {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+
+    width: f32,
+    height: f32,
+    plan_name: String,
+
+    /// the distance of the cursor from the top of the page
+    y: f32,
+    pages: usize,
+    heading: String,
+}
+
+impl Writer {
+    fn new(plan_name: &str, landscape: bool) -> Result<Self, String> {
+        let (width, height) = if landscape { (297.0, 210.0) } else { (210.0, 297.0) };
+        let (doc, page, layer) = PdfDocument::new(plan_name, Mm(width), Mm(height), "content");
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(|err| format!("could not add font: {err}"))?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|err| format!("could not add font: {err}"))?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Self {
+            doc, layer, regular, bold,
+            width, height,
+            plan_name: plan_name.to_owned(),
+            y: 0.0,
+            pages: 0,
+            heading: String::new(),
+        })
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        self.doc.save_to_bytes().map_err(|err| format!("could not write pdf: {err}"))
+    }
+
+    /// starts a new page with the plan name, the print date and the heading at the top
+    fn page(&mut self, heading: &str) {
+        // the document starts with an empty page
+        if self.pages > 0 {
+            let (page, layer) = self.doc.add_page(Mm(self.width), Mm(self.height), "content");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+        }
+        self.pages += 1;
+        self.heading = heading.to_owned();
+
+        let plan_name = self.plan_name.clone();
+        self.text(MARGIN, MARGIN, 8.0, false, &plan_name);
+        let printed = format!("printed {}", Local::now().format("%d.%m.%Y"));
+        self.text(self.width - MARGIN - text_width(&printed, 8.0), MARGIN, 8.0, false, &printed);
+        self.text(MARGIN, MARGIN + 6.0, 14.0, true, &fit(heading, self.width - 2.0 * MARGIN, 14.0));
+        self.hline(MARGIN + HEADER_HEIGHT - 4.0, MARGIN, self.width - MARGIN);
+
+        let page_number = format!("{}", self.pages);
+        self.text((self.width - text_width(&page_number, 8.0)) / 2.0, self.height - MARGIN / 2.0 - 3.0, 8.0, false, &page_number);
+
+        self.y = MARGIN + HEADER_HEIGHT;
+    }
+
+    /// breaks the page if less than `height` is left, returns whether the page was broken
+    fn ensure(&mut self, height: f32) -> bool {
+        if self.y + height > self.height - MARGIN - 4.0 {
+            let heading = format!("{} (continued)", self.heading.trim_end_matches(" (continued)"));
+            self.page(&heading);
+            true
+        } else { false }
+    }
+
+    fn subheading(&mut self, text: &str) {
+        self.ensure(LINE_HEIGHT * 4.0);
+        self.y += 2.0;
+        self.text(MARGIN, self.y, 11.0, true, text);
+        self.y += LINE_HEIGHT + 1.5;
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        let max_width = self.width - 2.0 * MARGIN;
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            if !line.is_empty() && text_width(&format!("{line} {word}"), 10.0) > max_width {
+                self.ensure(LINE_HEIGHT);
+                self.text(MARGIN, self.y, 10.0, false, &line);
+                self.y += LINE_HEIGHT;
+                line.clear();
+            }
+            if !line.is_empty() { line.push(' ') }
+            line.push_str(word);
+        }
+
+        self.ensure(LINE_HEIGHT);
+        self.text(MARGIN, self.y, 10.0, false, &line);
+        self.y += LINE_HEIGHT + 1.0;
+    }
+
+    fn row(&mut self, widths: &[f32], cells: &[&str], bold: bool) {
+        self.ensure(LINE_HEIGHT);
+        let mut x = MARGIN;
+        for (width, cell) in widths.iter().zip(cells) {
+            self.text(x, self.y, 9.0, bold, &fit(cell, width - 1.5, 9.0));
+            x += width;
+        }
+        self.y += LINE_HEIGHT;
+        if bold { self.hline(self.y - 1.2, MARGIN, x) }
+    }
+
+    /// a row of a table, the header is repeated if the page breaks
+    fn table_row(&mut self, widths: &[f32], header: &[&str], cells: &[&str]) {
+        if self.ensure(LINE_HEIGHT) { self.row(widths, header, true) }
+        self.row(widths, cells, false);
+    }
+
+    fn text(&self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        // the position is the top of the text, but pdf uses the baseline
+        let baseline = y + size * PT_TO_MM * 0.8;
+        self.layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+        self.layer.use_text(text, size, Mm(x), Mm(self.height - baseline), if bold { &self.bold } else { &self.regular });
+    }
+
+    fn rect(&self, x: f32, y: f32, width: f32, height: f32, fill: Option<(f32, f32, f32)>, stroke: bool) {
+        let mode = match (fill, stroke) {
+            (Some(_), true) => PaintMode::FillStroke,
+            (Some(_), false) => PaintMode::Fill,
+            (None, _) => PaintMode::Stroke,
+        };
+        if let Some((r, g, b)) = fill { self.layer.set_fill_color(Color::Rgb(Rgb::new(r, g, b, None))) }
+        self.layer.set_outline_color(Color::Rgb(Rgb::new(0.4, 0.4, 0.4, None)));
+        self.layer.set_outline_thickness(0.3);
+
+        self.layer.add_rect(Rect::new(Mm(x), Mm(self.height - y - height), Mm(x + width), Mm(self.height - y)).with_mode(mode));
+    }
+
+    fn line(&self, from: (f32, f32), to: (f32, f32)) {
+        self.layer.set_outline_color(Color::Rgb(Rgb::new(0.6, 0.6, 0.6, None)));
+        self.layer.set_outline_thickness(0.2);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(from.0), Mm(self.height - from.1)), false),
+                (Point::new(Mm(to.0), Mm(self.height - to.1)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    fn hline(&self, y: f32, from: f32, to: f32) { self.line((from, y), (to, y)) }
+    fn vline(&self, x: f32, from: f32, to: f32) { self.line((x, from), (x, to)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn test_plan() -> PlanerData {
+        let mut data = PlanerData::default();
+        let teacher = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let student = data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+        data.add_student("Mia".to_owned(), "Kraft".to_owned(), None);
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+
+        for (i, day) in [15, 16].into_iter().enumerate() {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), vec!["Eng".to_owned()], Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(teacher);
+            data.unfinished_exams[exam].examinees.push(student);
+            data.book_exam(exam, room, Utc.ymd(2022, 12, day).and_hms(8, 0, 0));
+            data.finish_exam(exam);
+        }
+
+        data
+    }
+
+    #[test]
+    fn page_breaks_by_day() {
+        let data = test_plan();
+        let bookings = data.bookings();
+
+        let mut writer = Writer::new("test", true).unwrap();
+        data.room_grid_pdf(&mut writer, &bookings);
+        assert_eq!(writer.pages, 2, "the room grid should have one page per day");
+
+        let mut writer = Writer::new("test", false).unwrap();
+        data.students_pdf(&mut writer, &bookings);
+        assert_eq!(writer.pages, 2, "there should be one letter per student");
+    }
+
+    #[test]
+    fn render_to_file() {
+        let data = test_plan();
+        for layout in PdfLayout::ALL {
+            let path = std::env::temp_dir().join(format!("planer-test-{}.pdf", layout.name().replace(' ', "-")));
+            data.export_pdf(layout, &path).unwrap();
+
+            let bytes = std::fs::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(bytes.starts_with(b"%PDF"), "{} should be a pdf file", layout.name());
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use rust_xlsxwriter::{Workbook, Format};
+
+use crate::planer::PlanerData;
+
+use super::Booking;
+
+/// how the csv file is written, other school software is often picky about this
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    /// quote every field instead of only the ones that need it
+    pub quote_all: bool,
+    /// `\r\n` instead of `\n`
+    pub crlf: bool,
+    /// prepend a byte order mark so excel detects utf-8
+    pub bom: bool,
+}
+
+impl CsvDialect {
+    pub const COMMA: CsvDialect = CsvDialect { delimiter: b',', quote_all: false, crlf: false, bom: false };
+    /// what excel expects in locales that use the comma as decimal separator
+    pub const EXCEL: CsvDialect = CsvDialect { delimiter: b';', quote_all: false, crlf: true, bom: true };
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self { CsvDialect::COMMA }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetFormat {
+    Csv(CsvDialect),
+    Xlsx,
+}
+
+impl SheetFormat {
+    pub const ALL: [SheetFormat; 3] = [SheetFormat::Csv(CsvDialect::COMMA), SheetFormat::Csv(CsvDialect::EXCEL), SheetFormat::Xlsx];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SheetFormat::Csv(dialect) if dialect.delimiter == b';' => "csv (semicolon)",
+            SheetFormat::Csv(_) => "csv (comma)",
+            SheetFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SheetFormat::Csv(_) => "csv",
+            SheetFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+const HEADER: [&str; 11] = ["date", "start", "end", "room", "id", "subjects", "examiners", "examiner names", "examinees", "tags", "error"];
+
+fn row(booking: &Booking) -> [String; 11] {
+    [
+        booking.start.format("%Y-%m-%d").to_string(),
+        booking.start.format("%H:%M").to_string(),
+        booking.end.format("%H:%M").to_string(),
+        booking.room.clone(),
+        booking.id.clone(),
+        booking.subjects.join(", "),
+        booking.examiner_shorthands(),
+        booking.examiners.iter().map(|v| format!("{}", v.name)).collect::<Vec<_>>().join(", "),
+        booking.examinee_names(),
+        booking.tags.join(", "),
+        booking.error.clone().unwrap_or_default(),
+    ]
+}
+
+impl PlanerData {
+    pub fn export_sheet(&self, format: SheetFormat, path: impl AsRef<Path>) -> Result<(), String> {
+        let bytes = self.render_sheet(format)?;
+        std::fs::write(path, bytes).map_err(|err| format!("could not write file: {err}"))
+    }
+
+    /// one row per booked exam, sorted like [`PlanerData::bookings`]
+    pub fn render_sheet(&self, format: SheetFormat) -> Result<Vec<u8>, String> {
+        let rows: Vec<_> = self.bookings().iter().map(row).collect();
+
+        match format {
+            SheetFormat::Csv(dialect) => render_csv(&rows, dialect),
+            SheetFormat::Xlsx => render_xlsx(&rows),
+        }
+    }
+}
+
+fn render_csv(rows: &[[String; 11]], dialect: CsvDialect) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    if dialect.bom { out.extend_from_slice("\u{feff}".as_bytes()) }
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(dialect.delimiter)
+        .quote_style(if dialect.quote_all { csv::QuoteStyle::Always } else { csv::QuoteStyle::Necessary })
+        .terminator(if dialect.crlf { csv::Terminator::CRLF } else { csv::Terminator::Any(b'\n') })
+    .from_writer(out);
+
+    writer.write_record(HEADER).map_err(|err| err.to_string())?;
+    for row in rows {
+        writer.write_record(row).map_err(|err| err.to_string())?;
+    }
+
+    writer.into_inner().map_err(|err| err.to_string())
+}
+
+fn render_xlsx(rows: &[[String; 11]]) -> Result<Vec<u8>, String> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("bookings").map_err(|err| err.to_string())?;
+
+    let bold = Format::new().set_bold();
+    for (col, name) in HEADER.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &bold).map_err(|err| err.to_string())?;
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        for (col, value) in row.iter().enumerate() {
+            if value.is_empty() { continue }
+            sheet.write_string(i as u32 + 1, col as u16, value).map_err(|err| err.to_string())?;
+        }
+    }
+
+    for (col, width) in [12, 7, 7, 10, 14, 20, 14, 30, 30, 20, 40].into_iter().enumerate() {
+        sheet.set_column_width(col as u16, width).map_err(|err| err.to_string())?;
+    }
+    sheet.set_freeze_panes(1, 0).map_err(|err| err.to_string())?;
+    sheet.autofilter(0, 0, rows.len() as u32, HEADER.len() as u16 - 1).map_err(|err| err.to_string())?;
+
+    workbook.save_to_buffer().map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use crate::planer::violation::{Conflict, Severity, Violation};
+
+    use super::*;
+
+    #[test]
+    fn export_csv_dialects() {
+        let mut data = PlanerData::default();
+        let teacher = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let exam = data.add_exam("eng-1".to_owned(), Duration::minutes(30), vec!["Eng".to_owned(), "Bio".to_owned()], Vec::new());
+        data.unfinished_exams[exam].examiners[0] = Some(teacher);
+        data.unfinished_exams[exam].violations.push(Violation::new(Severity::Error, "custom", Conflict::new("too many exams in a row", Vec::new())));
+        data.book_exam(exam, room, Utc.ymd(2022, 12, 15).and_hms(8, 0, 0));
+        data.finish_exam(exam);
+
+        let csv = String::from_utf8(data.render_sheet(SheetFormat::Csv(CsvDialect::COMMA)).unwrap()).unwrap();
+        assert_eq!(csv,
+            "date,start,end,room,id,subjects,examiners,examiner names,examinees,tags,error\n\
+             2022-12-15,08:00,08:30,A1.03,eng-1,\"Eng, Bio\",Ee,Falk Eertel,,,too many exams in a row\n");
+
+        let csv = String::from_utf8(data.render_sheet(SheetFormat::Csv(CsvDialect::EXCEL)).unwrap()).unwrap();
+        assert!(csv.starts_with("\u{feff}date;start;"));
+        assert!(csv.contains(";Eng, Bio;") && csv.ends_with("\r\n"));
+
+        let xlsx = data.render_sheet(SheetFormat::Xlsx).unwrap();
+        assert!(xlsx.starts_with(b"PK"), "xlsx files are zip archives");
+    }
+}
@@ -0,0 +1,319 @@
+pub mod ics;
+
+use std::path::Path;
+
+use chrono::Duration;
+
+use crate::planer::{PlanerData, Teacher, Student, Tag, default_shorthand, store::{Id, AsUuid}};
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        format!("{} added, {} updated, {} warnings", self.added, self.updated, self.warnings.len())
+    }
+}
+
+/// a csv table with case insensitive column lookup by header name
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<csv::StringRecord>,
+}
+
+impl Table {
+    fn read(content: &str) -> Result<Self, String> {
+        // spreadsheet programs in german locales export with ';' instead of ','
+        let header_line = content.lines().next().unwrap_or("");
+        let delimiter = if header_line.matches(';').count() > header_line.matches(',').count() { b';' } else { b',' };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+        let headers = reader.headers()
+            .map_err(|err| format!("could not read header: {err}"))?
+            .iter().map(|v| v.to_lowercase())
+        .collect();
+
+        let rows = reader.records()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("could not read row: {err}"))?;
+
+        Ok(Self { headers, rows })
+    }
+
+    fn column(&self, names: &[&str]) -> Option<usize> {
+        self.headers.iter().position(|v| names.contains(&&v[..]))
+    }
+
+    fn require_column(&self, names: &[&str]) -> Result<usize, String> {
+        self.column(names).ok_or_else(|| format!("missing column \"{}\"", names[0]))
+    }
+}
+
+fn field(row: &csv::StringRecord, column: Option<usize>) -> Option<String> {
+    column.and_then(|idx| row.get(idx))
+        .map(|v| v.trim().to_owned())
+    .filter(|v| !v.is_empty())
+}
+
+/// splits a list inside of a single field (e.g. `"De, Ges"`)
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    value.split([',', ';', '\n'])
+        .map(|v| v.trim().to_owned())
+        .filter(|v| !v.is_empty())
+    .collect()
+}
+
+impl PlanerData {
+    pub fn import_teachers(&mut self, path: impl AsRef<Path>) -> Result<ImportReport, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("could not open file: {err}"))?;
+        self.import_teachers_csv(&content)
+    }
+
+    /// imports teachers with the columns `first`, `last`, `title`, `shorthand` and `subjects`.
+    /// teachers with the same first and last name are updated in place,
+    /// a shorthand that is already used by another teacher is reported as a warning
+    pub fn import_teachers_csv(&mut self, content: &str) -> Result<ImportReport, String> {
+        let table = Table::read(content)?;
+        let first_col = table.require_column(&["first", "first name", "firstname"])?;
+        let last_col = table.require_column(&["last", "last name", "lastname"])?;
+        let title_col = table.column(&["title"]);
+        let shorthand_col = table.column(&["shorthand", "short"]);
+        let subjects_col = table.column(&["subjects", "subject"]);
+
+        let mut report = ImportReport::default();
+        for (i, row) in table.rows.iter().enumerate() {
+            // the header is line 1
+            let line = i + 2;
+            let (first, last) = match (field(row, Some(first_col)), field(row, Some(last_col))) {
+                (Some(first), Some(last)) => (first, last),
+                _ => {
+                    report.warnings.push(format!("line {line}: skipped, first or last name is missing"));
+                    continue;
+                },
+            };
+            let title = field(row, title_col);
+            let given_shorthand = field(row, shorthand_col);
+            let subjects = field(row, subjects_col).map(|v| split_list(&v)).unwrap_or_default();
+
+            let existing = self.teachers.iter()
+                .find(|v| v.name.first.eq_ignore_ascii_case(&first) && v.name.last.eq_ignore_ascii_case(&last))
+            .map(|v| v.id());
+
+            let shorthand = match (&given_shorthand, existing) {
+                (Some(shorthand), _) => shorthand.clone(),
+                (None, Some(existing)) => self.teachers[existing].shorthand.clone(),
+                (None, None) => default_shorthand(&last),
+            };
+
+            if let Some(other) = self.find_shorthand_collision(&shorthand, existing) {
+                report.warnings.push(format!("line {line}: the shorthand \"{shorthand}\" of {first} {last} is already used by {other}"));
+            }
+
+            if let Some(existing) = existing {
+                let teacher = &mut self.teachers[existing];
+                teacher.name.first = first;
+                teacher.name.last = last;
+                if title_col.is_some() { teacher.name.title = title }
+                teacher.shorthand = shorthand;
+                if subjects_col.is_some() { teacher.subjects = subjects }
+                report.updated += 1;
+            } else {
+                self.add_teacher(first, last, title, Some(shorthand), &subjects[..]);
+                report.added += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub fn import_exams(&mut self, path: impl AsRef<Path>) -> Result<ImportReport, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("could not open file: {err}"))?;
+        self.import_exams_csv(&content)
+    }
+
+    /// imports exams with the columns `id`, `subjects`, `duration` (in minutes), `students`,
+    /// `examiners` (shorthands) and `tags` (required tags end with a `*`).
+    /// students and examiners are resolved against the existing people,
+    /// every name that could not be resolved is reported as a warning
+    pub fn import_exams_csv(&mut self, content: &str) -> Result<ImportReport, String> {
+        let table = Table::read(content)?;
+        let id_col = table.require_column(&["id", "exam", "exam id"])?;
+        let subjects_col = table.column(&["subjects", "subject"]);
+        let duration_col = table.column(&["duration"]);
+        let students_col = table.column(&["students", "student", "examinees"]);
+        let examiners_col = table.column(&["examiners", "examiner", "teachers"]);
+        let tags_col = table.column(&["tags", "tag"]);
+
+        let mut report = ImportReport::default();
+        for (i, row) in table.rows.iter().enumerate() {
+            let line = i + 2;
+            let id = match field(row, Some(id_col)) {
+                Some(id) => id,
+                None => {
+                    report.warnings.push(format!("line {line}: skipped, the exam id is missing"));
+                    continue;
+                },
+            };
+
+            let duration = match field(row, duration_col).map(|v| v.parse::<i64>()) {
+                Some(Ok(minutes)) if minutes > 0 => Duration::minutes(minutes),
+                Some(_) => {
+                    report.warnings.push(format!("line {line}: invalid duration for {id}, using 30min"));
+                    Duration::minutes(30)
+                },
+                None => Duration::minutes(30),
+            };
+
+            let subjects = field(row, subjects_col).map(|v| split_list(&v)).unwrap_or_default();
+            let tags = field(row, tags_col).map(|v| split_list(&v)).unwrap_or_default()
+                .into_iter().map(|v| match v.strip_suffix('*') {
+                    Some(name) => Tag { name: name.trim().to_owned(), required: true },
+                    None => Tag { name: v, required: false },
+                })
+            .collect();
+
+            let mut examinees = Vec::new();
+            for name in field(row, students_col).map(|v| split_list(&v)).unwrap_or_default() {
+                match self.find_student(&name) {
+                    Ok(student) => examinees.push(student),
+                    Err(err) => report.warnings.push(format!("line {line}: {id}: {err}")),
+                }
+            }
+
+            let mut examiners = [None, None, None];
+            let mut n_examiners = 0;
+            for shorthand in field(row, examiners_col).map(|v| split_list(&v)).unwrap_or_default() {
+                let teacher = self.teachers.iter().find(|v| v.shorthand == shorthand);
+                match teacher {
+                    Some(_) if n_examiners >= examiners.len() => {
+                        report.warnings.push(format!("line {line}: {id}: at most {} examiners are supported, ignoring \"{shorthand}\"", examiners.len()));
+                    },
+                    Some(teacher) => {
+                        examiners[n_examiners] = Some(teacher.id());
+                        n_examiners += 1;
+                    },
+                    None => report.warnings.push(format!("line {line}: {id}: unknown examiner \"{shorthand}\"")),
+                }
+            }
+
+            let exam = self.add_exam(id, duration, subjects, tags);
+            let exam = &mut self.unfinished_exams[exam];
+            exam.examinees = examinees;
+            exam.examiners = examiners;
+            report.added += 1;
+        }
+
+        Ok(report)
+    }
+
+    /// finds a student by `first last` (or with title) ignoring case
+    fn find_student(&self, name: &str) -> Result<Id<Student>, String> {
+        let matches: Vec<_> = self.students.iter()
+            .filter(|v| {
+                let full_name = format!("{} {}", v.name.first, v.name.last);
+                full_name.eq_ignore_ascii_case(name) || format!("{}", v.name).eq_ignore_ascii_case(name)
+            })
+        .collect();
+
+        match &matches[..] {
+            [student] => Ok(student.id()),
+            [] => Err(format!("unknown student \"{name}\"")),
+            _ => Err(format!("the student name \"{name}\" is ambiguous ({} matches)", matches.len())),
+        }
+    }
+
+    fn find_shorthand_collision(&self, shorthand: &str, except: Option<Id<Teacher>>) -> Option<String> {
+        self.teachers.iter()
+            .filter(|v| Some(v.id()) != except)
+            .find_map(|v| {
+                if v.shorthand == shorthand { Some(format!("{}", v.name)) } else { None }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_teachers() {
+        let mut data = PlanerData::default();
+        let report = data.import_teachers_csv("first,last,title,shorthand,subjects\nAmanda,Adjei,,Adj,\"De, kath. Rel\"\nFalk,Eertel,Dr.,,Eng;Geo\n").unwrap();
+
+        assert_eq!(report.added, 2);
+        assert!(report.warnings.is_empty(), "unexpected warnings: {:?}", report.warnings);
+
+        let teacher = data.teachers.iter().nth(1).unwrap();
+        assert_eq!(teacher.shorthand, "Ee", "a missing shorthand should fall back to the first two letters of the last name");
+        assert_eq!(teacher.name.title.as_deref(), Some("Dr."));
+        assert_eq!(teacher.subjects, vec!["Eng".to_owned(), "Geo".to_owned()]);
+    }
+
+    #[test]
+    fn reimport_teachers_updates_in_place() {
+        let mut data = PlanerData::default();
+        data.import_teachers_csv("first;last;shorthand;subjects\nAmanda;Adjei;Adj;De\n").unwrap();
+        let report = data.import_teachers_csv("first;last;shorthand;subjects\nAmanda;Adjei;Adj;De, Eth\nTimur;Adams;;Ges\nPaula;Adler;;Mu\n").unwrap();
+
+        assert_eq!((report.added, report.updated), (2, 1));
+        assert_eq!(data.teachers.len(), 3, "re-importing should not duplicate teachers");
+        assert_eq!(data.teachers.iter().next().unwrap().subjects.len(), 2);
+        assert_eq!(report.warnings.len(), 1, "only the fallback shorthand of Adler should collide, got {:?}", report.warnings);
+    }
+
+    #[test]
+    fn reimport_without_titles_keeps_them() {
+        let mut data = PlanerData::default();
+        data.import_teachers_csv("first,last,title\nFalk,Eertel,Dr.\n").unwrap();
+        let report = data.import_teachers_csv("first,last,subjects\nFalk,Eertel,Eng\n").unwrap();
+
+        assert_eq!(report.updated, 1);
+        let teacher = data.teachers.iter().next().unwrap();
+        assert_eq!(teacher.name.title.as_deref(), Some("Dr."), "a missing title column should not remove the title");
+        assert_eq!(teacher.subjects, vec!["Eng".to_owned()]);
+    }
+
+    #[test]
+    fn colliding_shorthand_adds_a_new_teacher() {
+        let mut data = PlanerData::default();
+        data.import_teachers_csv("first,last,shorthand\nTimur,Adams,Ad\n").unwrap();
+        let report = data.import_teachers_csv("first,last,shorthand\nPaula,Adler,Ad\n").unwrap();
+
+        assert_eq!((report.added, report.updated), (1, 0));
+        assert_eq!(report.warnings.len(), 1, "the collision should be reported, got {:?}", report.warnings);
+        assert!(report.warnings[0].contains("Timur Adams"));
+
+        let names: Vec<_> = data.teachers.iter().map(|v| v.name.to_string()).collect();
+        assert_eq!(names, vec!["Timur Adams".to_owned(), "Paula Adler".to_owned()], "the existing teacher should not be renamed");
+    }
+
+    #[test]
+    fn import_exams() {
+        let mut data = PlanerData::default();
+        data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+        data.add_student("Mia".to_owned(), "Kraft".to_owned(), None);
+        data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, Some("Ee".to_owned()), &[]);
+
+        let report = data.import_exams_csv("id,subject,duration,students,examiners,tags\neng-1,Eng,20,\"jonas berg, Mia Kraft\",\"Ee, Xy\",\"lab*, quiet\"\neng-2,Eng,,Max Mustermann,Ee,\n").unwrap();
+
+        assert_eq!(report.added, 2);
+        assert_eq!(report.warnings.len(), 2, "the unknown examiner and student should be reported, got {:?}", report.warnings);
+
+        let exam = data.unfinished_exams.iter().next().unwrap();
+        assert_eq!(exam.duration, Duration::minutes(20));
+        assert_eq!(exam.examinees.len(), 2);
+        assert!(exam.examiners[0].is_some() && exam.examiners[1].is_none());
+        assert!(exam.tags[0].required && exam.tags[0].name == "lab" && !exam.tags[1].required);
+
+        assert_eq!(data.unfinished_exams.iter().nth(1).unwrap().duration, Duration::minutes(30), "a missing duration should default to 30min");
+    }
+}
@@ -0,0 +1,456 @@
+use std::{path::Path, collections::HashSet};
+
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+
+use crate::planer::{BlockedTime, calendar::{Calendar, Event}};
+
+use super::ImportReport;
+
+/// recurrences without an end are only expanded this far
+const MAX_RECURRENCE_DAYS: i64 = 366;
+const MAX_OCCURRENCES: usize = 1000;
+
+pub fn import_blocked_times(calendar: &mut Calendar<BlockedTime>, path: impl AsRef<Path>, zone: Tz) -> Result<ImportReport, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| format!("could not open file: {err}"))?;
+    import_blocked_times_ics(calendar, &content, zone)
+}
+
+/// adds all events of an iCalendar file to `calendar`, their times are converted to the wall clock of `zone`.
+/// events that were imported before (same uid) are replaced
+pub fn import_blocked_times_ics(calendar: &mut Calendar<BlockedTime>, content: &str, zone: Tz) -> Result<ImportReport, String> {
+    let (events, warnings) = parse_blocked_times(content, zone)?;
+    let uids: HashSet<_> = events.iter().map(|v| v.data.uid.clone()).collect();
+
+    let mut replaced = HashSet::new();
+    calendar.retain(|v| if uids.contains(&v.data.uid) {
+        replaced.insert(v.data.uid.clone());
+        false
+    } else { true });
+
+    for ev in events { calendar.add_event(ev) }
+
+    Ok(ImportReport {
+        added: uids.len() - replaced.len(),
+        updated: replaced.len(),
+        warnings,
+    })
+}
+
+/// parses the VEVENTs of an iCalendar file (RFC 5545) including simple recurrences.
+/// cancelled events and events marked as free are skipped
+pub fn parse_blocked_times(content: &str, zone: Tz) -> Result<(Vec<Event<BlockedTime>>, Vec<String>), String> {
+    let mut events = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut current: Option<VEvent> = None;
+    // depth of components nested in the current event (e.g. VALARM)
+    let mut nested = 0;
+    let mut found_calendar = false;
+    // every unknown timezone is only reported once
+    let mut ignored_zones = HashSet::new();
+
+    for line in unfold(content) {
+        let prop = match Property::parse(&line) { Some(v) => v, None => continue };
+
+        match (&prop.name[..], &prop.value.to_uppercase()[..]) {
+            ("BEGIN", "VCALENDAR") => found_calendar = true,
+            ("BEGIN", "VEVENT") => current = Some(VEvent::default()),
+            ("BEGIN", _) if current.is_some() => nested += 1,
+            ("END", "VEVENT") => if let Some(ev) = current.take() {
+                match ev.into_events(zone) {
+                    Ok((mut v, mut w)) => {
+                        events.append(&mut v);
+                        warnings.append(&mut w);
+                    },
+                    Err(err) => warnings.push(err),
+                }
+            },
+            ("END", _) if nested > 0 => nested -= 1,
+            _ => if let (Some(ev), 0) = (&mut current, nested) {
+                if let Some(tzid) = prop.param("TZID").filter(|v| parse_zone(v).is_none()) {
+                    if ignored_zones.insert(tzid.to_owned()) {
+                        warnings.push(format!("the timezone \"{tzid}\" is unknown, its times are taken as they are written"));
+                    }
+                }
+                if let Err(err) = ev.set(prop, zone) { warnings.push(err) }
+            },
+        }
+    }
+
+    if !found_calendar { return Err("the file is not an iCalendar file".to_owned()) }
+
+    Ok((events, warnings))
+}
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Self> {
+        // the value starts after the first colon that is not inside a quoted parameter
+        let mut in_quotes = false;
+        let split = line.char_indices().find(|(_, c)| {
+            if *c == '"' { in_quotes = !in_quotes }
+            *c == ':' && !in_quotes
+        })?.0;
+
+        let mut parts = line[..split].split(';');
+        let name = parts.next()?.trim().to_uppercase();
+        let params = parts.filter_map(|v| v.split_once('='))
+            .map(|(k, v)| (k.to_uppercase(), v.trim_matches('"').to_owned()))
+        .collect();
+
+        Some(Self { name, params, value: line[(split + 1)..].to_owned() })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == name).map(|(_, v)| &v[..])
+    }
+}
+
+#[derive(Default)]
+struct VEvent {
+    uid: Option<String>,
+    summary: Option<String>,
+    start: Option<(NaiveDateTime, bool)>,
+    end: Option<NaiveDateTime>,
+    duration: Option<Duration>,
+    rrule: Option<String>,
+    exdates: Vec<NaiveDateTime>,
+    skip: bool,
+}
+
+impl VEvent {
+    fn set(&mut self, prop: Property, zone: Tz) -> Result<(), String> {
+        let parse = |value: &str| parse_date_time(value, prop.param("VALUE"), prop.param("TZID"), zone);
+        match &prop.name[..] {
+            "UID" => self.uid = Some(prop.value),
+            "SUMMARY" => self.summary = Some(unescape(&prop.value)),
+            "DTSTART" => self.start = Some(parse(&prop.value)?),
+            "DTEND" => self.end = Some(parse(&prop.value)?.0),
+            "DURATION" => self.duration = Some(parse_duration(&prop.value)?),
+            "RRULE" => self.rrule = Some(prop.value),
+            "EXDATE" => for v in prop.value.split(',') {
+                self.exdates.push(parse(v)?.0);
+            },
+            "STATUS" if prop.value.eq_ignore_ascii_case("CANCELLED") => self.skip = true,
+            "TRANSP" if prop.value.eq_ignore_ascii_case("TRANSPARENT") => self.skip = true,
+            _ => {},
+        }
+
+        Ok(())
+    }
+
+    fn into_events(self, zone: Tz) -> Result<(Vec<Event<BlockedTime>>, Vec<String>), String> {
+        if self.skip { return Ok((Vec::new(), Vec::new())) }
+
+        let summary = self.summary.unwrap_or_else(|| "blocked".to_owned());
+        let (start, all_day) = self.start.ok_or_else(|| format!("the event \"{summary}\" has no start"))?;
+        let duration = match (self.end, self.duration) {
+            (Some(end), _) => end - start,
+            (None, Some(duration)) => duration,
+            (None, None) if all_day => Duration::days(1),
+            (None, None) => Duration::zero(),
+        };
+        if duration < Duration::zero() { return Err(format!("the event \"{summary}\" ends before it starts")) }
+        let uid = self.uid.unwrap_or_else(|| format!("{start}-{summary}"));
+
+        let mut warnings = Vec::new();
+        let starts = match &self.rrule {
+            Some(rrule) => expand_rrule(start, rrule, zone, &mut warnings).map_err(|err| format!("{summary}: {err}"))?,
+            None => vec![start],
+        };
+
+        let events = starts.into_iter()
+            .filter(|v| !self.exdates.contains(v))
+            .map(|v| Event::new(DateTime::from_utc(v, Utc), duration, BlockedTime {
+                uid: uid.clone(),
+                summary: summary.clone(),
+            }))
+        .collect();
+
+        Ok((events, warnings.into_iter().map(|v| format!("{summary}: {v}")).collect()))
+    }
+}
+
+/// joins lines that were folded (RFC 5545 3.1)
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => res.push('\n'),
+                Some(c) => res.push(c),
+                None => {},
+            }
+        } else { res.push(c) }
+    }
+
+    res
+}
+
+fn parse_zone(tzid: &str) -> Option<Tz> {
+    tzid.parse().ok()
+}
+
+/// parses a DATE or DATE-TIME value. the times of the plan are wall clock times in `zone`
+/// (like the exported calendars), so utc times and times with a known TZID are converted to it.
+/// dates, floating times and times with an unknown TZID are taken as they are written.
+/// returns the time and whether it is a whole day
+fn parse_date_time(value: &str, value_type: Option<&str>, tzid: Option<&str>, zone: Tz) -> Result<(NaiveDateTime, bool), String> {
+    let value = value.trim();
+    if value_type == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| format!("invalid date \"{value}\""))?;
+        return Ok((date.and_hms(0, 0, 0), true));
+    }
+
+    let (written, utc) = match value.strip_suffix('Z') {
+        Some(v) => (v, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(written, "%Y%m%dT%H%M%S").map_err(|_| format!("invalid date-time \"{value}\""))?;
+
+    let from = if utc { Some(Tz::UTC) } else { tzid.and_then(parse_zone) };
+    match from {
+        Some(from) => {
+            let time = from.from_local_datetime(&time).earliest().ok_or_else(|| format!("the time \"{value}\" does not exist in {from}"))?;
+            Ok((time.with_timezone(&zone).naive_local(), false))
+        },
+        None => Ok((time, false)),
+    }
+}
+
+/// parses a DURATION value like `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let err = || format!("invalid duration \"{value}\"");
+    let (sign, rest) = match value.trim().strip_prefix('-') {
+        Some(v) => (-1, v),
+        None => (1, value.trim().strip_prefix('+').unwrap_or(value.trim())),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(err)?;
+
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {},
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = number.parse().map_err(|_| err())?;
+                number.clear();
+                duration = duration + match c {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    _ => Duration::seconds(n),
+                };
+            },
+            _ => return Err(err()),
+        }
+    }
+
+    Ok(duration * sign)
+}
+
+/// expands the occurrences of a recurrence rule. supports `FREQ=DAILY|WEEKLY|MONTHLY`
+/// with `INTERVAL`, `COUNT`, `UNTIL` and `BYDAY` (weekly only)
+fn expand_rrule(start: NaiveDateTime, rrule: &str, zone: Tz, warnings: &mut Vec<String>) -> Result<Vec<NaiveDateTime>, String> {
+    let mut freq = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = None;
+    let mut by_day = Vec::new();
+
+    for part in rrule.split(';') {
+        let (key, value) = match part.split_once('=') { Some(v) => v, None => continue };
+        match &key.to_uppercase()[..] {
+            "FREQ" => freq = Some(value.to_uppercase()),
+            "INTERVAL" => interval = value.parse::<i64>().map_err(|_| format!("invalid interval \"{value}\""))?.max(1),
+            "COUNT" => count = Some(value.parse::<usize>().map_err(|_| format!("invalid count \"{value}\""))?),
+            "UNTIL" => until = Some(match parse_date_time(value, None, None, zone)? {
+                // a date is inclusive
+                (date, true) => date + Duration::days(1) - Duration::seconds(1),
+                (time, false) => time,
+            }),
+            "BYDAY" => for day in value.split(',') {
+                by_day.push(parse_weekday(day).ok_or_else(|| format!("unsupported BYDAY \"{day}\""))?);
+            },
+            "WKST" => {},
+            other => warnings.push(format!("the recurrence part {other} is not supported and was ignored")),
+        }
+    }
+
+    let mut occurrences = Occurrences {
+        start,
+        limit: until.unwrap_or(start + Duration::days(MAX_RECURRENCE_DAYS)),
+        max: count.unwrap_or(MAX_OCCURRENCES).min(MAX_OCCURRENCES),
+        res: Vec::new(),
+    };
+
+    match freq.as_deref() {
+        Some("DAILY") => {
+            let mut k = 0;
+            while occurrences.push(start + Duration::days(k * interval)) { k += 1 }
+        },
+        Some("WEEKLY") => {
+            if by_day.is_empty() { by_day.push(start.weekday()) }
+            by_day.sort_by_key(|v| v.num_days_from_monday());
+
+            let week_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+            let mut k = 0;
+            'weeks: loop {
+                let week = week_start + Duration::weeks(k * interval);
+                for day in &by_day {
+                    if !occurrences.push(week + Duration::days(day.num_days_from_monday() as i64)) { break 'weeks }
+                }
+                k += 1;
+            }
+        },
+        Some("MONTHLY") => {
+            let mut k = 0;
+            loop {
+                let months = start.month0() as i64 + k * interval;
+                let (year, month) = (start.year() + (months / 12) as i32, (months % 12) as u32 + 1);
+                if NaiveDate::from_ymd(year, month, 1).and_time(start.time()) > occurrences.limit { break }
+
+                // months without that day (e.g. the 31st) are skipped
+                if let Some(date) = NaiveDate::from_ymd_opt(year, month, start.day()) {
+                    if !occurrences.push(date.and_time(start.time())) { break }
+                }
+                k += 1;
+            }
+        },
+        Some(other) => {
+            warnings.push(format!("the recurrence frequency {other} is not supported, only the first occurrence was imported"));
+            occurrences.push(start);
+        },
+        None => return Err("the recurrence rule has no frequency".to_owned()),
+    }
+
+    Ok(occurrences.res)
+}
+
+struct Occurrences {
+    start: NaiveDateTime,
+    limit: NaiveDateTime,
+    max: usize,
+    res: Vec<NaiveDateTime>,
+}
+
+impl Occurrences {
+    /// adds an occurrence and returns whether more occurrences should be generated
+    fn push(&mut self, time: NaiveDateTime) -> bool {
+        if self.res.len() >= self.max || time > self.limit { return false }
+        if time >= self.start { self.res.push(time) }
+
+        self.res.len() < self.max
+    }
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    Some(match &day.trim().to_uppercase()[..] {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABSENCES: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+        BEGIN:VEVENT\r\nUID:conference@example.com\r\nSUMMARY:conference\\, berlin\r\nDTSTART:20221215T080000\r\nDTEND:20221215T120000\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:choir@example.com\r\nSUMMARY:cho\r\n ir\r\nDTSTART;TZID=Europe/Berlin:20221205T130500\r\nDURATION:PT1H30M\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=MO,TH;COUNT=4\r\nEXDATE;TZID=Europe/Berlin:20221208T130500\r\n\
+        BEGIN:VALARM\r\nACTION:DISPLAY\r\nSUMMARY:reminder\r\nEND:VALARM\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:free@example.com\r\nDTSTART;VALUE=DATE:20221216\r\nTRANSP:TRANSPARENT\r\nEND:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn parse_absences() {
+        let (events, warnings) = parse_blocked_times(ABSENCES, Tz::Europe__Berlin).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        let starts: Vec<_> = events.iter().map(|v| v.start.naive_utc()).collect();
+        assert_eq!(starts, vec![
+            NaiveDate::from_ymd(2022, 12, 15).and_hms(8, 0, 0),
+            NaiveDate::from_ymd(2022, 12, 5).and_hms(13, 5, 0),
+            // 8th of december is excluded
+            NaiveDate::from_ymd(2022, 12, 12).and_hms(13, 5, 0),
+            NaiveDate::from_ymd(2022, 12, 15).and_hms(13, 5, 0),
+        ]);
+
+        assert_eq!(events[0].data.summary, "conference, berlin");
+        assert_eq!(events[0].duration, Duration::hours(4));
+        assert_eq!(events[1].data.summary, "choir", "folded lines should be joined and nested components ignored");
+        assert_eq!(events[1].duration, Duration::minutes(90));
+    }
+
+    #[test]
+    fn reimport_replaces_events() {
+        let mut calendar = Calendar::new();
+        import_blocked_times_ics(&mut calendar, ABSENCES, Tz::Europe__Berlin).unwrap();
+        let report = import_blocked_times_ics(&mut calendar, &ABSENCES.replace("COUNT=4", "COUNT=2"), Tz::Europe__Berlin).unwrap();
+
+        assert_eq!((report.added, report.updated), (0, 2));
+        assert_eq!(calendar.events().len(), 2, "the previously imported occurrences should be replaced");
+    }
+
+    #[test]
+    fn times_are_converted_to_the_wall_clock_of_the_plan() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\nUID:1\r\nDTSTART:20221215T080000Z\r\nDTEND;TZID=UTC:20221215T093000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:2\r\nDTSTART;TZID=America/New_York:20221215T080000\r\nDURATION:PT1H\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:3\r\nDTSTART;TZID=Mars/Olympus:20221215T080000\r\nDURATION:PT1H\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let (events, warnings) = parse_blocked_times(ics, Tz::Europe__Berlin).unwrap();
+
+        assert_eq!(warnings, vec!["the timezone \"Mars/Olympus\" is unknown, its times are taken as they are written".to_owned()]);
+        assert_eq!(events[0].start, Utc.ymd(2022, 12, 15).and_hms(9, 0, 0), "08:00 utc is 09:00 in berlin");
+        assert_eq!(events[0].duration, Duration::minutes(90));
+        assert_eq!(events[1].start, Utc.ymd(2022, 12, 15).and_hms(14, 0, 0));
+        assert_eq!(events[2].start, Utc.ymd(2022, 12, 15).and_hms(8, 0, 0));
+    }
+
+    #[test]
+    fn events_ending_before_they_start_are_rejected() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nSUMMARY:backwards\r\nDTSTART:20221215T100000\r\nDTEND:20221215T080000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let (events, warnings) = parse_blocked_times(ics, Tz::Europe__Berlin).unwrap();
+
+        assert!(events.is_empty());
+        assert_eq!(warnings, vec!["the event \"backwards\" ends before it starts".to_owned()]);
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1W2D"), Ok(Duration::days(9)));
+        assert_eq!(parse_duration("-PT15M"), Ok(Duration::minutes(-15)));
+        assert!(parse_duration("1H").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+//! the scheduling domain of planer without any gui: the plan data, calendars,
+//! the constraint solver and the import and export formats
+
+pub mod export;
+pub mod import;
+pub mod planer;
+pub mod solver;
+
+pub use planer::{PlanerData, calendar::Calendar};
+pub use solver::{Constraints, solve};
@@ -0,0 +1,770 @@
+pub mod agenda;
+pub mod calendar;
+pub mod store;
+pub mod violation;
+
+use std::{path::Path, cell::RefCell, collections::BTreeSet};
+
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+use serde_with::{serde_as, DurationSeconds};
+use uuid::Uuid;
+
+use crate::solver::{Constraints, SolverConfig, SolveResult, solve, solve_incremental, rules::RuleSet};
+
+use self::{calendar::{Calendar, Event, TimeRange}, store::{Store, Id, AsUuid}, violation::{Violation, Severity, Conflict, Entity}};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct PlanerData {
+    pub students: Store<Student>,
+    pub teachers: Store<Teacher>,
+
+    pub unfinished_exams: Store<Exam>,
+    pub finished_exams: Store<Exam>,
+
+    pub rooms: Store<Room>,
+    pub timetable: Timetable,
+
+    /// saved with the plan, so the same schedule can be solved again
+    #[serde(default)]
+    pub solver: SolverConfig,
+    /// the times of the plan are wall clock times in this timezone, imported calendars are converted to it
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+
+    /// the configuration of the constraints, call [`PlanerData::apply_rules`] after changing it
+    #[serde(default)]
+    pub rules: RuleSet,
+    /// built from the rules
+    #[serde(skip)]
+    pub constraints: Constraints,
+
+    #[serde(skip)]
+    pub current_file_name: Option<String>,
+
+    #[serde(skip)]
+    needs_recompute: RefCell<bool>,
+}
+
+/// a borrowed, read only view of the plan that constraints are checked against
+#[derive(Clone, Copy)]
+pub struct Snapshot<'a> {
+    pub students: &'a Store<Student>,
+    pub teachers: &'a Store<Teacher>,
+    pub unfinished_exams: &'a Store<Exam>,
+    pub finished_exams: &'a Store<Exam>,
+    pub rooms: &'a Store<Room>,
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn exam(&self, id: Id<Exam>) -> Option<&'a Exam> {
+        self.finished_exams.get(id).or_else(|| self.unfinished_exams.get(id))
+    }
+}
+
+impl PlanerData {
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let data = serde_json::to_string(self).map_err(|err| format!("could not serialize data: {err}"))?;
+        std::fs::write(path, data).map_err(|err| format!("could not write file: {err}"))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        Self::try_load(path).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn load_template(path: impl AsRef<Path>) -> Self {
+        Self::try_load_template(path).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// like [`PlanerData::load`], but returns an error instead of panicking
+    pub fn try_load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let file_name = path.as_ref().to_str().unwrap().to_owned();
+        let mut data = Self::try_load_template(path)?;
+        data.current_file_name = Some(file_name);
+
+        Ok(data)
+    }
+
+    pub fn try_load_template(path: impl AsRef<Path>) -> Result<Self, String> {
+        let file = std::fs::read_to_string(path).map_err(|err| format!("could not open file: {err}"))?;
+        let mut data: PlanerData = serde_json::from_str(&file[..]).map_err(|err| format!("could not deserialize data: {err}"))?;
+        data.apply_rules();
+
+        Ok(data)
+    }
+
+    /// saves the current state of the plan, so it can be [restored](Self::restore) later
+    pub fn checkpoint(&self, label: impl Into<String>) -> Result<Checkpoint, String> {
+        let data = serde_json::to_string(self).map_err(|err| format!("could not serialize data: {err}"))?;
+        Ok(Checkpoint { label: label.into(), data })
+    }
+
+    /// rebuilds the constraints from the rules and checks the plan against them
+    pub fn apply_rules(&mut self) {
+        self.constraints = self.rules.build();
+        self.compute_conflicts();
+    }
+
+    /// resets the plan to a checkpoint, the constraints and the file name are kept
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<(), String> {
+        let data: PlanerData = serde_json::from_str(&checkpoint.data).map_err(|err| format!("could not deserialize data: {err}"))?;
+        self.replace_plan(data);
+
+        Ok(())
+    }
+
+    /// whether the plan is still in the state it was in when `checkpoint` was taken
+    pub fn is_at(&self, checkpoint: &Checkpoint) -> bool {
+        matches!(serde_json::to_string(self), Ok(data) if data == checkpoint.data)
+    }
+
+    /// replaces the plan with `other`, the constraints and the file name are kept
+    pub fn replace_plan(&mut self, other: PlanerData) {
+        self.students = other.students;
+        self.teachers = other.teachers;
+        self.unfinished_exams = other.unfinished_exams;
+        self.finished_exams = other.finished_exams;
+        self.rooms = other.rooms;
+        self.timetable = other.timetable;
+        self.solver = other.solver;
+        self.compute_conflicts();
+    }
+
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        Snapshot {
+            students: &self.students,
+            teachers: &self.teachers,
+            unfinished_exams: &self.unfinished_exams,
+            finished_exams: &self.finished_exams,
+            rooms: &self.rooms,
+        }
+    }
+
+    /// finds an exam whether it is finished or not
+    pub fn exam(&self, id: Id<Exam>) -> Option<&Exam> {
+        self.finished_exams.get(id).or_else(|| self.unfinished_exams.get(id))
+    }
+
+    pub fn exam_mut(&mut self, id: Id<Exam>) -> Option<&mut Exam> {
+        match self.finished_exams.get_mut(id) {
+            Some(exam) => Some(exam),
+            None => self.unfinished_exams.get_mut(id),
+        }
+    }
+
+    pub fn add_student(&mut self, first: String, last: String, title: Option<String>) -> Id<Student> {
+        self.students.insert(Student {
+            name: Name { uuid: Uuid::new_v4(), first, last, title },
+            calendar: Calendar::new(),
+            buffer: Duration::zero(),
+        })
+    }
+
+    pub fn solve(&mut self) -> Result<SolveResult, SolveResult> {
+        self.solve_on(Utc::today())
+    }
+
+    /// places the unfinished exams on `day`, fails if not all of them could be placed
+    pub fn solve_on(&mut self, day: Date<Utc>) -> Result<SolveResult, SolveResult> {
+        let res = solve(self, day);
+        self.compute_conflicts();
+        res
+    }
+
+    /// places the unfinished exams on `day` and moves as few unpinned exams as possible to fix conflicts,
+    /// fails if not all of them could be placed
+    pub fn solve_incremental_on(&mut self, day: Date<Utc>) -> Result<SolveResult, SolveResult> {
+        let res = solve_incremental(self, day);
+        self.compute_conflicts();
+        res
+    }
+
+    /// checks every booked exam against all constraints
+    pub fn compute_conflicts(&mut self) {
+        let plan = self.snapshot();
+        let violations: Vec<_> = self.finished_exams.iter()
+            .map(|exam| {
+                let mut violations = match exam.pairing.as_ref().map(|(room, time)| (plan.rooms.get(*room), time)) {
+                    Some((Some(room), time)) => self.constraints.violations(exam, &(room, time), &plan),
+                    Some((None, _)) => vec![Violation::new(Severity::Error, "room exists", Conflict::new("the room of this exam does not exist anymore", vec![Entity::Exam(exam.id())]))],
+                    None => vec![Violation::new(Severity::Error, "booked", Conflict::new("this exam is finished but has no room and time", vec![Entity::Exam(exam.id())]))],
+                };
+                for violation in &mut violations {
+                    violation.acknowledged = violation.can_acknowledge() && exam.acknowledged.contains(&violation.key());
+                }
+
+                (exam.id(), violations)
+            })
+        .collect();
+
+        for (id, violations) in violations {
+            self.finished_exams[id].violations = violations;
+        }
+    }
+
+    pub fn schedule_recompute(&self) {
+        *self.needs_recompute.borrow_mut() = true;
+    }
+
+    pub fn recompute_if_scheduled(&mut self) {
+        if *self.needs_recompute.borrow() {
+            self.compute_conflicts();
+            *self.needs_recompute.borrow_mut() = false;
+        }
+    }
+
+    pub fn add_teacher(&mut self, first: String, last: String, title: Option<String>, shorthand: Option<String>, subjects: &[String]) -> Id<Teacher> {
+        let shorthand = shorthand.unwrap_or_else(|| default_shorthand(&last));
+        self.teachers.insert(Teacher {
+            name: Name { uuid: Uuid::new_v4(), first, last, title },
+            shorthand,
+            calendar: Calendar::new(),
+            blocked: Calendar::new(),
+            subjects: subjects.to_vec(),
+            buffer: Duration::zero(),
+        })
+    }
+
+    /// adds the exam to the calendars of the room and all participants
+    pub fn book_exam(&mut self, exam_id: Id<Exam>, room_id: Id<Room>, start_time: DateTime<Utc>) {
+        if !self.rooms.contains(room_id) { return }
+        let exam = match self.exam_mut(exam_id) { Some(v) => v, None => return };
+        exam.pairing = Some((room_id, start_time));
+
+        let ev = Event::new(start_time, exam.duration, exam_id);
+        let (examinees, examiners) = (exam.examinees.clone(), exam.examiners);
+
+        for student in examinees {
+            if let Some(v) = self.students.get_mut(student) { v.calendar.add_event(ev.clone()) }
+        }
+
+        for teacher in examiners.into_iter().flatten() {
+            if let Some(v) = self.teachers.get_mut(teacher) { v.calendar.add_event(ev.clone()) }
+        }
+
+        self.rooms[room_id].calendar.add_event(ev);
+    }
+
+    /// removes the exam from the calendars it was booked into
+    pub fn unbook_exam(&mut self, exam_id: Id<Exam>) {
+        let exam = match self.exam_mut(exam_id) { Some(v) => v, None => return };
+        let (room_id, start_time) = match exam.pairing.take() { Some(v) => v, None => return };
+
+        let ev = Event::new(start_time, exam.duration, exam_id);
+        let (examinees, examiners) = (exam.examinees.clone(), exam.examiners);
+
+        for student in examinees {
+            if let Some(v) = self.students.get_mut(student) { v.calendar.remove_event(&ev); }
+        }
+
+        for teacher in examiners.into_iter().flatten() {
+            if let Some(v) = self.teachers.get_mut(teacher) { v.calendar.remove_event(&ev); }
+        }
+
+        if let Some(room) = self.rooms.get_mut(room_id) { room.calendar.remove_event(&ev); }
+    }
+
+    /// moves a booked exam to `start` in the same room, unless a hard constraint forbids the new time
+    pub fn move_exam(&mut self, exam_id: Id<Exam>, start: DateTime<Utc>) -> Result<(), String> {
+        let plan = self.snapshot();
+        let exam = plan.finished_exams.get(exam_id).ok_or("only booked exams can be moved")?;
+        let room = match exam.pairing {
+            Some((room, _)) => room,
+            None => return Err("only booked exams can be moved".to_owned()),
+        };
+        let candidate = (plan.rooms.get(room).ok_or("the room of this exam does not exist anymore")?, &start);
+        self.constraints.apply_hard(exam, &candidate, &plan, false).map_err(|v| v.key())?;
+
+        self.unbook_exam(exam_id);
+        self.book_exam(exam_id, room, start);
+        Ok(())
+    }
+
+    /// unbooks all finished exams in `scope` that are not pinned and moves them back to the unfinished exams
+    pub fn clear_unpinned(&mut self, scope: ClearScope) -> Vec<Id<Exam>> {
+        let cleared: Vec<_> = self.finished_exams.iter()
+            .filter(|exam| !exam.pinned)
+            .filter(|exam| match (scope, exam.pairing) {
+                (ClearScope::All, _) => true,
+                (_, None) => false,
+                (ClearScope::Day(day), Some((_, start))) => start.date() == day,
+                (ClearScope::Room(room), Some((booked, _))) => booked == room,
+            })
+            .map(|exam| exam.id())
+        .collect();
+
+        for exam in &cleared {
+            self.unbook_exam(*exam);
+            self.unfinish_exam(*exam);
+        }
+
+        cleared
+    }
+
+    /// counts what is booked on `day`
+    pub fn day_summary(&self, day: Date<Utc>) -> DaySummary {
+        let mut summary = DaySummary::default();
+        let (mut rooms, mut teachers, mut students) = (BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+
+        for exam in self.finished_exams.iter() {
+            let room = match exam.pairing {
+                Some((room, start)) if start.date() == day => room,
+                _ => continue,
+            };
+
+            summary.exams += 1;
+            if exam.pinned { summary.pinned += 1 }
+            if exam.worst_open() == Some(Severity::Error) { summary.conflicts += 1 }
+            summary.warnings += exam.violations.iter().filter(|v| v.is_open() && v.severity == Severity::Warning).count();
+
+            rooms.insert(room);
+            teachers.extend(exam.examiners.iter().flatten().copied());
+            students.extend(exam.examinees.iter().copied());
+        }
+
+        summary.rooms = rooms.len();
+        summary.teachers = teachers.len();
+        summary.students = students.len();
+        summary
+    }
+
+    pub fn unfinish_exam(&mut self, exam: Id<Exam>) {
+        if let Some(exam) = self.finished_exams.remove(exam) {
+            self.unfinished_exams.insert(exam);
+        }
+    }
+
+    pub fn finish_exam(&mut self, exam: Id<Exam>) {
+        if let Some(exam) = self.unfinished_exams.remove(exam) {
+            self.finished_exams.insert(exam);
+        }
+    }
+
+    /// the ranges in `window` in which the exam fits into a room with all required tags while
+    /// the room, the examiners and the examinees are free. bookings of the exam itself are ignored
+    pub fn find_slots(&self, exam_id: Id<Exam>, window: TimeRange) -> Vec<Slot> {
+        let exam = match self.exam(exam_id) { Some(v) => v, None => return Vec::new() };
+        let other = |ev: &Event<Id<Exam>>| ev.data != exam_id;
+
+        let mut people_busy = Vec::new();
+        for teacher in exam.examiners.iter().filter_map(|v| self.teachers.get((*v)?)) {
+            people_busy.extend(teacher.calendar.busy_where(window, teacher.buffer, other));
+            people_busy.extend(teacher.blocked.busy(window, Duration::zero()));
+        }
+        for student in exam.examinees.iter().filter_map(|v| self.students.get(*v)) {
+            people_busy.extend(student.calendar.busy_where(window, student.buffer, other));
+        }
+
+        self.rooms.iter()
+            .filter(|room| exam.tags.iter().all(|tag| !tag.required || room.tags.contains(&tag.name)))
+            .flat_map(|room| {
+                let mut busy = people_busy.clone();
+                busy.extend(room.calendar.busy_where(window, room.buffer, other));
+                busy.extend(room.blocked.busy(window, Duration::zero()));
+
+                window.subtract(&TimeRange::merge(busy)).into_iter()
+                    .filter(|range| range.duration() >= exam.duration)
+                    .map(|range| Slot { room: room.id(), range })
+                .collect::<Vec<_>>()
+            })
+        .collect()
+    }
+
+    pub fn add_exam(&mut self, id: String, duration: Duration, subjects: Vec<String>, tags: Vec<Tag>) -> Id<Exam> {
+        self.unfinished_exams.insert(Exam {
+            duration, id, subjects, tags,
+            preparation: Duration::zero(),
+            uuid: Uuid::new_v4(),
+            examinees: Vec::new(),
+            pinned: false,
+            examiners: [None, None, None],
+            pairing: None,
+            acknowledged: Vec::new(),
+            violations: Vec::new(),
+        })
+    }
+
+    pub fn add_room(&mut self, number: String, tags: Vec<String>) -> Id<Room> {
+        self.rooms.insert(Room {
+            number, tags,
+            calendar: Calendar::new(),
+            blocked: Calendar::new(),
+            uuid: Uuid::new_v4(),
+            buffer: Duration::zero(),
+        })
+    }
+}
+
+
+impl Clone for PlanerData {
+    fn clone(&self) -> Self {
+        Self {
+            students: self.students.clone(),
+            teachers: self.teachers.clone(),
+
+            unfinished_exams: self.unfinished_exams.clone(),
+            finished_exams: self.finished_exams.clone(),
+            rooms: self.rooms.clone(),
+            timetable: self.timetable.clone(),
+            solver: self.solver,
+            timezone: self.timezone,
+
+            rules: self.rules.clone(),
+            constraints: self.constraints.clone(),
+            current_file_name: self.current_file_name.clone(),
+            needs_recompute: RefCell::new(*self.needs_recompute.borrow()),
+        }
+    }
+}
+
+impl Default for PlanerData {
+    fn default() -> Self {
+        let v = Self {
+            students: Store::new(),
+            teachers: Store::new(),
+
+            unfinished_exams: Store::new(),
+            finished_exams: Store::new(),
+            rooms: Store::new(),
+            timetable: Timetable::default(),
+            solver: SolverConfig::default(),
+            timezone: default_timezone(),
+
+            rules: RuleSet::default(),
+            constraints: Constraints::default(),
+            current_file_name: None,
+            needs_recompute: RefCell::new(false),
+        };
+        
+        v
+    }
+}
+
+fn default_timezone() -> Tz {
+    Tz::Europe__Berlin
+}
+
+/// the shorthand used for teachers if none is given (the first two letters of the last name)
+pub fn default_shorthand(last: &str) -> String {
+    last.chars().take(2).collect()
+}
+
+// small components
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Name {
+    uuid: Uuid,
+    pub first: String,
+    pub last: String,
+    pub title: Option<String>,
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}{} {}", self.first, self.title.as_ref().map(|v| format!(" {v}")).unwrap_or(String::new()), self.last))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LessonType {
+    Lesson,
+    Break,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timetable {
+    pub times: Vec<TimetableLesson>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimetableLesson {
+    pub start: NaiveTime,
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub duration: Duration,
+    pub lesson_type: LessonType,
+}
+
+impl Default for Timetable {
+    fn default() -> Self {
+        use LessonType::*;
+        let times = [
+            (NaiveTime::from_hms(08, 00, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(08, 50, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(09, 40, 00), Duration::minutes(45), Lesson),
+            // 20 min break
+            (NaiveTime::from_hms(10, 40, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(11, 30, 00), Duration::minutes(45), Lesson),
+
+            (NaiveTime::from_hms(12, 15, 00), Duration::minutes(45), Break), // 45 min break
+
+            (NaiveTime::from_hms(13, 05, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(13, 55, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(14, 45, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(15, 30, 00), Duration::minutes(45), Lesson),
+            (NaiveTime::from_hms(16, 15, 00), Duration::minutes(45), Lesson),
+        ].into_iter().map(|(start, duration, lesson_type)| TimetableLesson { start, duration, lesson_type }).collect();
+
+        Self { times }
+    }
+}
+
+/// which exams [`PlanerData::clear_unpinned`] removes from the plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClearScope {
+    All,
+    Day(Date<Utc>),
+    Room(Id<Room>),
+}
+
+/// what is booked on a day, see [`PlanerData::day_summary`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DaySummary {
+    pub exams: usize,
+    pub pinned: usize,
+    pub rooms: usize,
+    pub teachers: usize,
+    pub students: usize,
+    /// exams with an error
+    pub conflicts: usize,
+    /// warnings that are not acknowledged
+    pub warnings: usize,
+}
+
+/// a saved state of the plan, see [`PlanerData::checkpoint`]
+pub struct Checkpoint {
+    pub label: String,
+    data: String,
+}
+
+/// a range of time in which an exam fits into a room, see [`PlanerData::find_slots`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    pub room: Id<Room>,
+    pub range: TimeRange,
+}
+
+impl Slot {
+    /// whether an exam of `duration` can start at `start` in this slot
+    pub fn fits(&self, start: DateTime<Utc>, duration: Duration) -> bool {
+        self.range.contains(&TimeRange::from_duration(start, duration))
+    }
+}
+
+/// time in which a room or person is not available (e.g. an imported appointment)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedTime {
+    /// the uid of the imported event, used to replace it on re-import
+    pub uid: String,
+    pub summary: String,
+}
+
+// facilities
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Room {
+    uuid: Uuid,
+    pub calendar: Calendar<Id<Exam>>,
+    #[serde(default)]
+    pub blocked: Calendar<BlockedTime>,
+    pub number: String,
+    pub tags: Vec<String>,
+    /// the time that has to stay free between two exams in this room (e.g. to air it out)
+    #[serde_as(as = "DurationSeconds<i64>")]
+    #[serde(default = "Duration::zero")]
+    pub buffer: Duration,
+}
+impl AsUuid for Room { fn as_uuid(&self) -> Uuid { self.uuid } }
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exam {
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub duration: Duration,
+    /// the time the examinees get to prepare right before the exam
+    #[serde_as(as = "DurationSeconds<i64>")]
+    #[serde(default = "Duration::zero")]
+    pub preparation: Duration,
+    pub uuid: Uuid,
+    pub id: String,
+    pub pinned: bool,
+
+    pub examinees: Vec<Id<Student>>,
+    pub examiners: [Option<Id<Teacher>>; 3],
+
+    pub subjects: Vec<String>,
+    pub tags: Vec<Tag>,
+
+    pub pairing: Option<(Id<Room>, DateTime<Utc>)>,
+
+    /// the warnings the user has seen and accepted, see [`Violation::key`]
+    #[serde(default)]
+    pub acknowledged: Vec<String>,
+
+    #[serde(skip)]
+    pub violations: Vec<Violation>,
+}
+impl AsUuid for Exam { fn as_uuid(&self) -> Uuid { self.uuid } }
+
+
+// people
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Student {
+    pub name: Name,
+    pub calendar: Calendar<Id<Exam>>,
+    /// the time that has to stay free between two exams of this student
+    #[serde_as(as = "DurationSeconds<i64>")]
+    #[serde(default = "Duration::zero")]
+    pub buffer: Duration,
+}
+impl AsUuid for Student { fn as_uuid(&self) -> Uuid { self.name.uuid } }
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Teacher {
+    pub name: Name,
+    pub shorthand: String,
+    pub calendar: Calendar<Id<Exam>>,
+    #[serde(default)]
+    pub blocked: Calendar<BlockedTime>,
+    pub subjects: Vec<String>,
+    /// the time that has to stay free between two exams of this teacher
+    #[serde_as(as = "DurationSeconds<i64>")]
+    #[serde(default = "Duration::zero")]
+    pub buffer: Duration,
+}
+impl AsUuid for Teacher { fn as_uuid(&self) -> Uuid { self.name.uuid } }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_slots_intersects_participants_and_rooms() {
+        let at = |h, m| Utc.ymd(2022, 12, 15).and_hms(h, m, 0);
+        let mut data = PlanerData::default();
+        let lab = data.add_room("lab".to_owned(), vec!["physics".to_owned()]);
+        data.add_room("A1.03".to_owned(), Vec::new());
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let jonas = data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+
+        let tags = vec![Tag { name: "physics".to_owned(), required: true }];
+        let exam = data.add_exam("phy-1".to_owned(), Duration::minutes(30), Vec::new(), tags);
+        data.unfinished_exams[exam].examiners[0] = Some(falk);
+        data.unfinished_exams[exam].examinees.push(jonas);
+
+        // the teacher examines another exam in the lab at 9 and has a meeting at 11
+        let other = data.add_exam("eng-1".to_owned(), Duration::minutes(60), Vec::new(), Vec::new());
+        data.unfinished_exams[other].examiners[0] = Some(falk);
+        data.book_exam(other, lab, at(9, 0));
+        data.finish_exam(other);
+        data.teachers[falk].blocked.add_event(Event::new(at(11, 0), Duration::minutes(20), BlockedTime { uid: "1".to_owned(), summary: "meeting".to_owned() }));
+
+        let slots = data.find_slots(exam, TimeRange::new(at(8, 0), at(12, 0)));
+        assert!(slots.iter().all(|v| v.room == lab), "only rooms with the required tags should be offered");
+        assert_eq!(slots.iter().map(|v| v.range).collect::<Vec<_>>(), vec![
+            TimeRange::new(at(8, 0), at(9, 0)),
+            TimeRange::new(at(10, 0), at(11, 0)),
+            TimeRange::new(at(11, 20), at(12, 0)),
+        ]);
+
+        data.book_exam(exam, lab, at(8, 0));
+        assert_eq!(data.find_slots(exam, TimeRange::new(at(8, 0), at(9, 0))).len(), 1, "the booking of the exam itself should not block it");
+
+        assert!(slots[1].fits(at(10, 30), Duration::minutes(30)));
+        assert!(!slots[1].fits(at(10, 45), Duration::minutes(30)));
+    }
+
+    #[test]
+    fn clear_unpinned_and_restore() {
+        let at = |d, h| Utc.ymd(2022, 12, d).and_hms(h, 0, 0);
+        let mut data = PlanerData::default();
+        let rooms = [data.add_room("A1.03".to_owned(), Vec::new()), data.add_room("A1.04".to_owned(), Vec::new())];
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+
+        let bookings = [(rooms[0], at(15, 8)), (rooms[1], at(15, 9)), (rooms[0], at(16, 8)), (rooms[1], at(16, 9))];
+        let exams: Vec<_> = bookings.iter().enumerate().map(|(i, (room, start))| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(falk);
+            data.book_exam(exam, *room, *start);
+            data.finish_exam(exam);
+            exam
+        }).collect();
+        data.finished_exams[exams[0]].pinned = true;
+
+        let checkpoint = data.checkpoint("clear").unwrap();
+
+        assert_eq!(data.clear_unpinned(ClearScope::Day(at(16, 0).date())), vec![exams[2], exams[3]]);
+        assert_eq!(data.clear_unpinned(ClearScope::Room(rooms[0])), Vec::new(), "the pinned exam should stay");
+        assert_eq!(data.clear_unpinned(ClearScope::All), vec![exams[1]]);
+        assert_eq!(data.finished_exams.len(), 1);
+        assert_eq!(data.teachers[falk].calendar.events().len(), 1, "the cleared exams should be removed from the calendars");
+        assert!(data.unfinished_exams.iter().all(|v| v.pairing.is_none()));
+
+        data.restore(&checkpoint).unwrap();
+        assert_eq!(data.finished_exams.len(), 4);
+        assert_eq!(data.teachers[falk].calendar.events().len(), 4);
+        assert_eq!(data.rooms[rooms[1]].calendar.events().len(), 2);
+    }
+
+    #[test]
+    fn move_exam_checks_constraints() {
+        let at = |h, m| Utc.ymd(2022, 12, 15).and_hms(h, m, 0);
+        let mut data = PlanerData::default();
+        let rooms = [data.add_room("A1.03".to_owned(), Vec::new()), data.add_room("A1.04".to_owned(), Vec::new())];
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+
+        let exams: Vec<_> = rooms.iter().enumerate().map(|(i, room)| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(falk);
+            data.book_exam(exam, *room, at(8 + i as u32, 0));
+            data.finish_exam(exam);
+            exam
+        }).collect();
+
+        let err = data.move_exam(exams[1], at(8, 15)).unwrap_err();
+        assert!(err.contains("Falk Eertel: eng-0"), "{err}");
+        assert_eq!(data.finished_exams[exams[1]].pairing, Some((rooms[1], at(9, 0))), "a forbidden move should keep the exam in place");
+
+        data.move_exam(exams[1], at(10, 0)).unwrap();
+        assert_eq!(data.finished_exams[exams[1]].pairing, Some((rooms[1], at(10, 0))), "the exam should stay in its room");
+        let starts: Vec<_> = data.teachers[falk].calendar.events().iter().map(|v| v.start).collect();
+        assert_eq!(starts, vec![at(8, 0), at(10, 0)]);
+
+        let unbooked = data.add_exam("eng-2".to_owned(), Duration::minutes(30), Vec::new(), Vec::new());
+        assert!(data.move_exam(unbooked, at(8, 0)).is_err(), "unbooked exams can not be moved");
+    }
+
+    #[test]
+    fn day_summary_counts_bookings_of_the_day() {
+        let at = |d, h| Utc.ymd(2022, 12, d).and_hms(h, 0, 0);
+        let mut data = PlanerData::default();
+        let rooms = [data.add_room("A1.03".to_owned(), Vec::new()), data.add_room("A1.04".to_owned(), Vec::new())];
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let jonas = data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+
+        let bookings = [(rooms[0], at(15, 8)), (rooms[1], at(15, 8)), (rooms[0], at(16, 8))];
+        for (i, (room, start)) in bookings.into_iter().enumerate() {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(falk);
+            data.unfinished_exams[exam].examinees.push(jonas);
+            data.book_exam(exam, room, start);
+            data.finish_exam(exam);
+        }
+        data.compute_conflicts();
+
+        let summary = data.day_summary(at(15, 0).date());
+        assert_eq!((summary.exams, summary.rooms, summary.teachers, summary.students), (2, 2, 1, 1));
+        assert_eq!(summary.conflicts, 2, "both exams at 8:00 should conflict with each other");
+        assert_eq!(data.day_summary(at(16, 0).date()), DaySummary { exams: 1, rooms: 1, teachers: 1, students: 1, ..Default::default() });
+        assert_eq!(data.day_summary(at(17, 0).date()), DaySummary::default());
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::BTreeMap, cmp::Reverse};
+
+use chrono::prelude::*;
+
+use super::{store::{Id, AsUuid}, Exam, PlanerData, Room, Student, Teacher};
+
+/// an exam as it is listed in the agenda of a student
+pub struct AgendaEntry<'a> {
+    pub exam: &'a Exam,
+    /// `None` if the exam has no room and time yet
+    pub booking: Option<(&'a Room, DateTime<Utc>)>,
+    pub examiners: Vec<&'a Teacher>,
+}
+
+impl AgendaEntry<'_> {
+    /// when the student has to be there, the preparation time comes before the exam
+    pub fn arrival(&self) -> Option<DateTime<Utc>> {
+        Some(self.booking?.1 - self.exam.preparation)
+    }
+}
+
+/// how many exams a student has and where their plan needs attention
+pub struct StudentSummary {
+    pub student: Id<Student>,
+    pub exams: usize,
+    /// the first day with the most exams and their number
+    pub busiest_day: Option<(Date<Utc>, usize)>,
+    /// exams without a room
+    pub unbooked: usize,
+}
+
+impl StudentSummary {
+    /// whether the student has more than `max_per_day` exams on a day or an exam without a room
+    pub fn is_flagged(&self, max_per_day: usize) -> bool {
+        self.unbooked > 0 || matches!(self.busiest_day, Some((_, n)) if n > max_per_day)
+    }
+}
+
+impl PlanerData {
+    /// the exams of a student, the booked ones by their start and the unbooked ones after them
+    pub fn student_agenda(&self, student: Id<Student>) -> Vec<AgendaEntry<'_>> {
+        let mut agenda: Vec<_> = self.finished_exams.iter().chain(self.unfinished_exams.iter())
+            .filter(|exam| exam.examinees.contains(&student))
+            .map(|exam| AgendaEntry {
+                exam,
+                booking: exam.pairing.and_then(|(room, start)| Some((self.rooms.get(room)?, start))),
+                examiners: exam.examiners.iter().filter_map(|v| self.teachers.get((*v)?)).collect(),
+            })
+        .collect();
+
+        agenda.sort_by_key(|v| (v.booking.is_none(), v.booking.map(|(_, start)| start)));
+        agenda
+    }
+
+    pub fn student_summaries(&self) -> Vec<StudentSummary> {
+        self.students.iter().map(|student| {
+            let agenda = self.student_agenda(student.id());
+
+            let mut per_day: BTreeMap<Date<Utc>, usize> = BTreeMap::new();
+            for (_, start) in agenda.iter().filter_map(|v| v.booking) {
+                *per_day.entry(start.date()).or_default() += 1;
+            }
+
+            StudentSummary {
+                student: student.id(),
+                exams: agenda.len(),
+                busiest_day: per_day.into_iter().max_by_key(|&(day, n)| (n, Reverse(day))),
+                unbooked: agenda.iter().filter(|v| v.booking.is_none()).count(),
+            }
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn agenda_and_summaries() {
+        let at = |d, h| Utc.ymd(2022, 12, d).and_hms(h, 0, 0);
+        let mut data = PlanerData::default();
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let falk = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let jonas = data.add_student("Jonas".to_owned(), "Berg".to_owned(), None);
+        data.add_student("Mia".to_owned(), "Roth".to_owned(), None);
+
+        let exams: Vec<_> = [Some(at(15, 10)), None, Some(at(15, 8)), Some(at(16, 8))].into_iter().enumerate().map(|(i, start)| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examinees.push(jonas);
+            data.unfinished_exams[exam].examiners[0] = Some(falk);
+            if let Some(start) = start {
+                data.book_exam(exam, room, start);
+                data.finish_exam(exam);
+            }
+            exam
+        }).collect();
+        data.finished_exams[exams[2]].preparation = Duration::minutes(20);
+
+        let agenda = data.student_agenda(jonas);
+        let order: Vec<_> = agenda.iter().map(|v| v.exam.id()).collect();
+        assert_eq!(order, vec![exams[2], exams[0], exams[3], exams[1]], "booked exams should come first, by their start");
+        assert_eq!(agenda[0].arrival(), Some(at(15, 8) - Duration::minutes(20)));
+        assert_eq!(agenda[0].examiners[0].id(), falk);
+        assert!(agenda[3].booking.is_none());
+
+        let summaries = data.student_summaries();
+        assert_eq!(summaries[0].exams, 4);
+        assert_eq!(summaries[0].busiest_day, Some((at(15, 0).date(), 2)));
+        assert_eq!(summaries[0].unbooked, 1);
+        assert!(summaries[0].is_flagged(2), "an exam without a room should be flagged");
+        assert!(!summaries[1].is_flagged(2));
+
+        data.book_exam(exams[1], room, at(16, 10));
+        data.finish_exam(exams[1]);
+        let summary = &data.student_summaries()[0];
+        assert!(!summary.is_flagged(2));
+        assert!(summary.is_flagged(1));
+    }
+}
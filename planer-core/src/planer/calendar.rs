@@ -0,0 +1,368 @@
+use chrono::{prelude::*, Duration, serde::ts_seconds}; 
+use serde::{Serialize, Deserialize};
+use serde_with::{serde_as, DurationSeconds};
+
+/// events are kept sorted by their start, so lookups can binary search for the
+/// first event that might overlap instead of checking every event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CalendarData<E>")]
+pub struct Calendar<E> {
+    events: Vec<Event<E>>,
+    /// the longest duration of all events, events starting earlier than this before a time can not include it
+    #[serde(skip_serializing)]
+    max_duration: Duration,
+}
+
+#[derive(Deserialize)]
+struct CalendarData<E> {
+    events: Vec<Event<E>>,
+}
+
+impl<E> From<CalendarData<E>> for Calendar<E> {
+    fn from(data: CalendarData<E>) -> Self {
+        let mut calendar = Self::new();
+        calendar.events = data.events;
+        calendar.events.sort_by_key(|v| v.start);
+        calendar.update_max_duration();
+
+        calendar
+    }
+}
+
+impl<E> Calendar<E> {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            max_duration: Duration::zero(),
+        }
+    }
+
+    /// inserts the event after all events with the same start
+    pub fn add_event(&mut self, ev: Event<E>) {
+        let idx = self.events.partition_point(|v| v.start <= ev.start);
+        self.max_duration = self.max_duration.max(ev.duration);
+        self.events.insert(idx, ev);
+    }
+
+    /// all events sorted by their start
+    pub fn events(&self) -> &[Event<E>] {
+        &self.events
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&Event<E>) -> bool) {
+        self.events.retain(f);
+        self.update_max_duration();
+    }
+
+    fn update_max_duration(&mut self) {
+        self.max_duration = self.events.iter().map(|v| v.duration).max().unwrap_or_else(Duration::zero);
+    }
+
+    /// the range of events that start between `start - max_duration` and `end`,
+    /// only these can overlap with a range from `start` to `end`
+    fn candidates(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> std::ops::Range<usize> {
+        let earliest = *start - self.max_duration;
+        let from = self.events.partition_point(|v| v.start < earliest);
+        let to = from + self.events[from..].partition_point(|v| &v.start <= end);
+
+        from..to
+    }
+
+
+    pub fn is_booked_at(&self, time: &DateTime<Utc>) -> bool {
+        self.events[self.candidates(time, time)].iter().any(|v| v.includes_time(time))
+    }
+
+    pub fn get_events_at(&self, time: &DateTime<Utc>) -> Vec<&Event<E>> {
+        self.events[self.candidates(time, time)].iter().filter(|v| v.includes_time(time)).collect()
+    }
+
+    /// the start of the returned events must not be changed, it would break the order
+    pub fn get_events_at_mut(&mut self, time: &DateTime<Utc>) -> Vec<&mut Event<E>> {
+        let range = self.candidates(time, time);
+        self.events[range].iter_mut().filter(|v| v.includes_time(time)).collect()
+    }
+
+
+    /// like [`Self::is_booked_from_to`], but events closer than `buffer` before or after the range also count
+    pub fn is_booked_with_buffer(&self, time: &DateTime<Utc>, duration: Duration, buffer: Duration) -> bool {
+        self.is_booked_from_to(&(*time - buffer), duration + buffer * 2)
+    }
+
+    pub fn get_booked_with_buffer(&self, time: &DateTime<Utc>, duration: Duration, buffer: Duration) -> Vec<&Event<E>> {
+        self.get_booked_from_to(&(*time - buffer), duration + buffer * 2)
+    }
+
+    pub fn is_booked_from_to(&self, time: &DateTime<Utc>, duration: Duration) -> bool {
+        self.events[self.candidates(time, &(*time + duration))].iter().any(|v| v.includes(time, duration))
+    }
+
+    pub fn get_booked_from_to(&self, time: &DateTime<Utc>, duration: Duration) -> Vec<&Event<E>> {
+        self.events[self.candidates(time, &(*time + duration))].iter().filter(|v| v.includes(time, duration)).collect()
+    }
+
+    /// the start of the returned events must not be changed, it would break the order
+    pub fn get_booked_from_to_mut(&mut self, time: &DateTime<Utc>, duration: Duration) -> Vec<&mut Event<E>> {
+        let range = self.candidates(time, &(*time + duration));
+        self.events[range].iter_mut().filter(|v| v.includes(time, duration)).collect()
+    }
+
+
+    /// the merged ranges in `window` in which an event (extended by `buffer` on both sides) takes place
+    pub fn busy(&self, window: TimeRange, buffer: Duration) -> Vec<TimeRange> {
+        self.busy_where(window, buffer, |_| true)
+    }
+
+    /// like [`Self::busy`], but only events for which `f` returns true count
+    pub fn busy_where(&self, window: TimeRange, buffer: Duration, f: impl Fn(&Event<E>) -> bool) -> Vec<TimeRange> {
+        let range = self.candidates(&(window.start - buffer), &(window.end + buffer));
+        let busy = self.events[range].iter()
+            .filter(|v| f(v))
+            .filter_map(|v| TimeRange::new(v.start - buffer, v.end() + buffer).intersect(&window))
+        .collect();
+
+        TimeRange::merge(busy)
+    }
+
+    /// the ranges in `window` that are not [busy](Self::busy)
+    pub fn free(&self, window: TimeRange, buffer: Duration) -> Vec<TimeRange> {
+        window.subtract(&self.busy(window, buffer))
+    }
+}
+
+impl<E> Default for Calendar<E> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<E: Eq> Calendar<E> {
+    /// removes the first event with the same data, the start of `ev` is only used to find it faster
+    pub fn remove_event(&mut self, ev: &Event<E>) -> Option<Event<E>> {
+        let from = self.events.partition_point(|v| v.start < ev.start);
+        let idx = self.events[from..].iter().position(|v| v.data == ev.data).map(|idx| from + idx)
+            .or_else(|| self.events[..from].iter().position(|v| v.data == ev.data))?;
+
+        Some(self.events.remove(idx))
+    }
+}
+
+/// a half-open range of time, like an event without data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn from_duration(start: DateTime<Utc>, duration: Duration) -> Self {
+        Self { start, end: start + duration }
+    }
+
+    /// the whole day in utc
+    pub fn day(day: Date<Utc>) -> Self {
+        Self::from_duration(day.and_hms(0, 0, 0), Duration::days(1))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, other: &TimeRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// the overlap of both ranges, ranges that only touch do not overlap
+    pub fn intersect(&self, other: &TimeRange) -> Option<TimeRange> {
+        let range = TimeRange::new(self.start.max(other.start), self.end.min(other.end));
+        (range.start < range.end).then_some(range)
+    }
+
+    /// sorts the ranges and joins the ones that overlap or touch
+    pub fn merge(mut ranges: Vec<TimeRange>) -> Vec<TimeRange> {
+        ranges.sort_by_key(|v| v.start);
+
+        let mut merged: Vec<TimeRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+
+    /// the parts of this range that are not covered by `busy`, which has to be [merged](Self::merge)
+    pub fn subtract(&self, busy: &[TimeRange]) -> Vec<TimeRange> {
+        let mut free = Vec::new();
+        let mut start = self.start;
+        for range in busy {
+            if range.end <= start { continue }
+            if range.start >= self.end { break }
+            if range.start > start { free.push(TimeRange::new(start, range.start)) }
+            start = start.max(range.end);
+        }
+        if start < self.end { free.push(TimeRange::new(start, self.end)) }
+
+        free
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event<T> {
+    #[serde(with = "ts_seconds")]
+    pub start: DateTime<Utc>,
+    #[serde_as(as = "DurationSeconds<i64>")]
+    pub duration: Duration,
+    pub data: T,
+}
+
+impl<T> Event<T> {
+    pub fn new(start: DateTime<Utc>, duration: Duration, data: T) -> Self {
+        Self { start, duration, data }
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + self.duration
+    }
+
+    /// events are half-open, they include their start but not their end
+    pub fn includes_time(&self, time: &DateTime<Utc>) -> bool {
+        &self.start <= time && time < &self.end()
+    }
+
+    /// whether the event overlaps the half-open range from `start` to `start + duration`,
+    /// an event ending exactly at `start` does not
+    pub fn includes(&self, start: &DateTime<Utc>, duration: Duration) -> bool {
+        start < &self.end() && self.start < *start + duration
+    }
+}
+
+impl<T: Clone> Clone for Event<T> {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            duration: self.duration,
+            data: self.data.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_includes_time() {
+        let event = Event {
+            start: Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 00, 00),
+            duration: Duration::hours(1),
+            data: (),
+        };
+
+        let time_1 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 30, 00);
+        let time_2 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(09, 00, 00);
+        let time_3 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 00, 00);
+        let time_4 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(10, 00, 00);
+        let time_5 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(06, 00, 00);
+
+        assert!( event.includes_time(&time_1), "the event should include {time_1:?}");
+        assert!(!event.includes_time(&time_2), "the event should not include {time_2:?} (exclusive at the end)");
+        assert!( event.includes_time(&time_3), "the event should include {time_3:?} (inclusive at the start)");
+        assert!(!event.includes_time(&time_4), "the event should not include {time_4:?} (after end)");
+        assert!(!event.includes_time(&time_5), "the event should not include {time_5:?} (before start)");
+    }
+
+    #[test]
+    fn event_includes() {
+        let event = Event {
+            start: Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 00, 00),
+            duration: Duration::hours(1),
+            data: (),
+        };
+
+        let time_1 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 30, 00);
+        let time_2 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(07, 30, 00);
+        let time_3 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(07, 29, 00);
+        let time_4 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(09, 01, 00);
+        let time_5 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(07, 30, 00) + Duration::seconds(1);
+        let time_6 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(09, 00, 00);
+        let time_7 = Utc.ymd(2022, Month::July.number_from_month(), 2).and_hms(08, 59, 00);
+
+        let duration = Duration::minutes(30);
+
+        assert!( event.includes(&time_1, duration), "the event should include the 30min range from {time_1:?}");
+        assert!(!event.includes(&time_2, duration), "the event should not include the 30min range from {time_2:?} (it ends at the start of the event)");
+        assert!( event.includes(&time_5, duration), "the event should include the 30min range from {time_5:?} (it ends a second after the start of the event)");
+        assert!(!event.includes(&time_3, duration), "the event should not include the 30min range from {time_3:?}");
+        assert!(!event.includes(&time_4, duration), "the event should not include the 30min range from {time_4:?}");
+        assert!(!event.includes(&time_6, duration), "the event should not include the 30min range from {time_6:?} (it starts at the end of the event)");
+        assert!( event.includes(&time_7, duration), "the event should include the 30min range from {time_7:?}");
+    }
+
+    #[test]
+    fn calendar_buffer() {
+        let start = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+        let mut calendar = Calendar::new();
+        calendar.add_event(Event::new(start, Duration::minutes(30), ()));
+
+        let after = start + Duration::minutes(30);
+        let buffer = Duration::minutes(5);
+        assert!(!calendar.is_booked_from_to(&after, Duration::minutes(30)), "back to back events should not overlap");
+        assert!( calendar.is_booked_with_buffer(&after, Duration::minutes(30), buffer), "the buffer should be kept after the event");
+        assert!(!calendar.is_booked_with_buffer(&(after + buffer), Duration::minutes(30), buffer), "an event after the buffer should fit");
+        assert!( calendar.is_booked_with_buffer(&(start - Duration::minutes(32)), Duration::minutes(30), buffer), "the buffer should be kept before the event");
+        assert!(!calendar.is_booked_with_buffer(&(start - Duration::minutes(35)), Duration::minutes(30), buffer), "an event before the buffer should fit");
+    }
+
+    #[test]
+    fn calendar_lookups_match_linear_scan() {
+        let day = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+        let mut calendar = Calendar::new();
+        // unordered, overlapping and one long event that starts long before the others
+        for (i, (start, duration)) in [(90, 30), (0, 45), (30, 15), (-120, 240), (200, 10), (90, 5)].into_iter().enumerate() {
+            calendar.add_event(Event::new(day + Duration::minutes(start), Duration::minutes(duration), i));
+        }
+
+        assert!(calendar.events().windows(2).all(|v| v[0].start <= v[1].start), "the events should be sorted by start");
+
+        for minute in (-180..260).step_by(5) {
+            let time = day + Duration::minutes(minute);
+            let linear: Vec<_> = calendar.events().iter().filter(|v| v.includes_time(&time)).map(|v| v.data).collect();
+            let indexed: Vec<_> = calendar.get_events_at(&time).into_iter().map(|v| v.data).collect();
+            assert_eq!(linear, indexed, "the events at {time:?} should match");
+
+            let linear: Vec<_> = calendar.events().iter().filter(|v| v.includes(&time, Duration::minutes(20))).map(|v| v.data).collect();
+            let indexed: Vec<_> = calendar.get_booked_from_to(&time, Duration::minutes(20)).into_iter().map(|v| v.data).collect();
+            assert_eq!(linear, indexed, "the events from {time:?} should match");
+        }
+
+        calendar.remove_event(&Event::new(day, Duration::zero(), 3));
+        assert!(!calendar.is_booked_at(&(day - Duration::minutes(60))), "the long event should be removed");
+
+        let json = serde_json::to_string(&calendar).unwrap();
+        let loaded: Calendar<usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.max_duration, Duration::minutes(45), "the max duration should be restored when loading");
+    }
+
+    #[test]
+    fn calendar_free_ranges() {
+        let at = |h, m| Utc.ymd(2022, 12, 15).and_hms(h, m, 0);
+        let mut calendar = Calendar::new();
+        calendar.add_event(Event::new(at(9, 0), Duration::minutes(30), ()));
+        calendar.add_event(Event::new(at(9, 15), Duration::minutes(30), ()));
+        calendar.add_event(Event::new(at(11, 0), Duration::minutes(60), ()));
+
+        let window = TimeRange::new(at(8, 0), at(12, 0));
+        assert_eq!(calendar.busy(window, Duration::zero()), vec![TimeRange::new(at(9, 0), at(9, 45)), TimeRange::new(at(11, 0), at(12, 0))], "overlapping events should be merged");
+        assert_eq!(calendar.free(window, Duration::zero()), vec![TimeRange::new(at(8, 0), at(9, 0)), TimeRange::new(at(9, 45), at(11, 0))]);
+        assert_eq!(calendar.free(window, Duration::minutes(5)), vec![TimeRange::new(at(8, 0), at(8, 55)), TimeRange::new(at(9, 50), at(10, 55))], "the buffer should shrink the free ranges");
+
+        let short = TimeRange::new(at(9, 30), at(10, 0));
+        assert_eq!(calendar.free(short, Duration::zero()), vec![TimeRange::new(at(9, 45), at(10, 0))], "the free ranges should be clipped to the window");
+    }
+}
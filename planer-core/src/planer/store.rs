@@ -0,0 +1,212 @@
+use std::{collections::HashMap, marker::PhantomData, hash::Hash};
+
+use serde::{Deserialize, Serialize, Deserializer, Serializer};
+use uuid::Uuid;
+
+/// a typed reference to an entity in a [`Store`], it is serialized as `{ "uuid": ... }`
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Id<T> {
+    uuid: Uuid,
+    #[serde(skip)]
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    pub fn new(uuid: Uuid) -> Self {
+        Self { uuid, marker: PhantomData }
+    }
+
+    pub fn uuid(&self) -> Uuid { self.uuid }
+}
+
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self { *self }
+}
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid == other.uuid
+    }
+}
+impl<T> Eq for Id<T> {}
+
+impl<T> PartialOrd for Id<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> { Some(self.cmp(other)) }
+}
+
+impl<T> Ord for Id<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering { self.uuid.cmp(&other.uuid) }
+}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) { self.uuid.hash(state) }
+}
+
+impl<T> std::fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Id").field(&self.uuid).finish()
+    }
+}
+
+
+pub trait AsUuid {
+    fn as_uuid(&self) -> Uuid;
+
+    fn id(&self) -> Id<Self> where Self: Sized {
+        Id::new(self.as_uuid())
+    }
+}
+
+/// owns entities in insertion order and finds them by their uuid.
+/// the uuid of a stored entity must not be changed, otherwise it can not be found anymore
+#[derive(Debug, Clone)]
+pub struct Store<T> {
+    items: Vec<T>,
+    index: HashMap<Uuid, usize>,
+}
+
+impl<T> Store<T> {
+    pub fn new() -> Self {
+        Self { items: Vec::new(), index: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize { self.items.len() }
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> { self.items.iter() }
+    pub fn as_slice(&self) -> &[T] { &self.items }
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> { self.items.iter_mut() }
+
+    pub fn get(&self, id: Id<T>) -> Option<&T> {
+        self.index.get(&id.uuid).map(|&idx| &self.items[idx])
+    }
+
+    pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+        self.index.get(&id.uuid).map(|&idx| &mut self.items[idx])
+    }
+
+    pub fn contains(&self, id: Id<T>) -> bool {
+        self.index.contains_key(&id.uuid)
+    }
+
+    /// the position in insertion order
+    pub fn position(&self, id: Id<T>) -> Option<usize> {
+        self.index.get(&id.uuid).copied()
+    }
+}
+
+impl<T: AsUuid> Store<T> {
+    /// adds an entity, an entity with the same uuid is replaced in place
+    pub fn insert(&mut self, item: T) -> Id<T> {
+        let id = item.id();
+        match self.index.get(&id.uuid) {
+            Some(&idx) => self.items[idx] = item,
+            None => {
+                self.index.insert(id.uuid, self.items.len());
+                self.items.push(item);
+            },
+        }
+
+        id
+    }
+
+    /// removes an entity and keeps the order of the others
+    pub fn remove(&mut self, id: Id<T>) -> Option<T> {
+        let idx = self.index.remove(&id.uuid)?;
+        let item = self.items.remove(idx);
+        self.reindex(idx);
+
+        Some(item)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = Id<T>> + '_ {
+        self.items.iter().map(|v| v.id())
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        self.items.retain(f);
+        self.index.clear();
+        self.reindex(0);
+    }
+
+    fn reindex(&mut self, from: usize) {
+        for (idx, item) in self.items.iter().enumerate().skip(from) {
+            self.index.insert(item.as_uuid(), idx);
+        }
+    }
+}
+
+impl<T> Default for Store<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T> std::ops::Index<Id<T>> for Store<T> {
+    type Output = T;
+
+    fn index(&self, id: Id<T>) -> &T {
+        self.get(id).expect("no entity with this id")
+    }
+}
+
+impl<T> std::ops::IndexMut<Id<T>> for Store<T> {
+    fn index_mut(&mut self, id: Id<T>) -> &mut T {
+        self.get_mut(id).expect("no entity with this id")
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Store<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter { self.items.iter() }
+}
+
+impl<T: AsUuid> FromIterator<T> for Store<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut store = Self::new();
+        for item in iter { store.insert(item); }
+
+        store
+    }
+}
+
+// stores are written as plain lists, like the vectors they replaced
+impl<T: Serialize> Serialize for Store<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + AsUuid> Deserialize<'de> for Store<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Item(Uuid, &'static str);
+    impl AsUuid for Item { fn as_uuid(&self) -> Uuid { self.0 } }
+
+    #[test]
+    fn remove_keeps_order_and_index() {
+        let mut store = Store::new();
+        let a = store.insert(Item(Uuid::new_v4(), "a"));
+        let b = store.insert(Item(Uuid::new_v4(), "b"));
+        let c = store.insert(Item(Uuid::new_v4(), "c"));
+
+        assert_eq!(store.remove(a).map(|v| v.1), Some("a"));
+        assert!(store.get(a).is_none());
+        assert_eq!(store[b].1, "b");
+        assert_eq!(store[c].1, "c");
+        assert_eq!(store.iter().map(|v| v.1).collect::<Vec<_>>(), vec!["b", "c"]);
+
+        let json = serde_json::to_string(&store.ids().collect::<Vec<_>>()).unwrap();
+        assert_eq!(json, format!("[{{\"uuid\":\"{}\"}},{{\"uuid\":\"{}\"}}]", b.uuid(), c.uuid()), "ids should be written like the old references");
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use super::{store::Id, Exam, Room, Student, Teacher};
+
+/// how bad a violation is, only errors keep the solver from placing an exam somewhere
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    #[default]
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Error, Severity::Warning, Severity::Info];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// an entity that takes part in a violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Entity {
+    Exam(Id<Exam>),
+    Room(Id<Room>),
+    Teacher(Id<Teacher>),
+    Student(Id<Student>),
+}
+
+/// what a constraint reports if a candidate violates it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub message: String,
+    pub involved: Vec<Entity>,
+}
+
+impl Conflict {
+    pub fn new(message: impl Into<String>, involved: Vec<Entity>) -> Self {
+        Self { message: message.into(), involved }
+    }
+}
+
+/// a [`Conflict`] together with the rule that raised it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub severity: Severity,
+    /// the name of the rule
+    pub rule: String,
+    pub message: String,
+    pub involved: Vec<Entity>,
+    /// warnings and infos can be acknowledged, so they are not shown as problems anymore
+    pub acknowledged: bool,
+}
+
+impl Violation {
+    pub fn new(severity: Severity, rule: impl Into<String>, conflict: Conflict) -> Self {
+        Self { severity, rule: rule.into(), message: conflict.message, involved: conflict.involved, acknowledged: false }
+    }
+
+    /// identifies the violation in [`Exam::acknowledged`], it changes when the exam is moved
+    pub fn key(&self) -> String {
+        format!("{}: {}", self.rule, self.message)
+    }
+
+    pub fn can_acknowledge(&self) -> bool {
+        self.severity != Severity::Error
+    }
+
+    /// whether the violation still needs attention
+    pub fn is_open(&self) -> bool {
+        !self.acknowledged
+    }
+}
+
+impl Exam {
+    pub fn has_errors(&self) -> bool {
+        self.violations.iter().any(|v| v.severity == Severity::Error)
+    }
+
+    /// the worst severity of the violations that are not acknowledged
+    pub fn worst_open(&self) -> Option<Severity> {
+        self.violations.iter().filter(|v| v.is_open()).map(|v| v.severity).max()
+    }
+
+    /// the messages of all errors, one per line
+    pub fn error_message(&self) -> Option<String> {
+        let errors: Vec<_> = self.violations.iter()
+            .filter(|v| v.severity == Severity::Error)
+            .map(|v| v.message.clone())
+        .collect();
+
+        if errors.is_empty() { None } else { Some(errors.join("\n")) }
+    }
+
+    /// acknowledges the violation or takes the acknowledgement back, errors can not be acknowledged
+    pub fn toggle_acknowledged(&mut self, violation: usize) {
+        let violation = match self.violations.get_mut(violation) {
+            Some(v) if v.can_acknowledge() => v,
+            _ => return,
+        };
+
+        violation.acknowledged = !violation.acknowledged;
+        let key = violation.key();
+        if violation.acknowledged {
+            self.acknowledged.push(key);
+        } else {
+            self.acknowledged.retain(|v| *v != key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use crate::planer::PlanerData;
+
+    use super::*;
+
+    #[test]
+    fn violations_are_collected_and_acknowledged() {
+        let mut data = PlanerData::default();
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let start = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+        let exams: Vec<_> = (0..2).map(|i| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.book_exam(exam, room, start);
+            data.finish_exam(exam);
+            exam
+        }).collect();
+        data.compute_conflicts();
+
+        let exam = &data.finished_exams[exams[1]];
+        let severities: Vec<_> = exam.violations.iter().map(|v| v.severity).collect();
+        assert_eq!(severities, vec![Severity::Error, Severity::Warning], "all violations should be collected, not only the first");
+        assert!(exam.violations[0].involved.contains(&Entity::Exam(exams[0])));
+
+        let exam = &mut data.finished_exams[exams[1]];
+        exam.toggle_acknowledged(0);
+        exam.toggle_acknowledged(1);
+        assert_eq!(exam.acknowledged.len(), 1, "errors can not be acknowledged");
+
+        let json = serde_json::to_string(&data).unwrap();
+        let mut data: PlanerData = serde_json::from_str(&json).unwrap();
+        data.compute_conflicts();
+        let exam = &data.finished_exams[exams[1]];
+        assert!(exam.violations[1].acknowledged, "acknowledgements should be saved");
+        assert_eq!(exam.worst_open(), Some(Severity::Error));
+    }
+}
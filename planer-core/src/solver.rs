@@ -0,0 +1,515 @@
+pub mod background;
+pub mod rules;
+pub mod script;
+
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::planer::{PlanerData, Snapshot, Exam, Room, Timetable, store::{Id, AsUuid}, violation::{Conflict, Severity, Violation}};
+
+use self::rules::RuleSet;
+
+
+// constraints are shared with solver threads, so they have to be Send + Sync
+pub type HardFn = dyn Fn(&Exam, &(&Room, &DateTime<Utc>), &Snapshot, bool) -> Result<(), Conflict> + Send + Sync;
+pub type SoftFn = dyn Fn(&Exam, &(&Room, &DateTime<Utc>), &Snapshot) -> i32 + Send + Sync;
+
+#[derive(Clone)]
+pub struct HardConstraint {
+    /// the name of the rule the constraint was built from
+    pub rule: String,
+    /// only errors keep the solver from placing an exam, the rest is just reported
+    pub severity: Severity,
+    pub func: Arc<HardFn>,
+}
+
+impl HardConstraint {
+    pub fn new(rule: impl Into<String>, severity: Severity, func: Arc<HardFn>) -> Self {
+        Self { rule: rule.into(), severity, func }
+    }
+
+    fn check(&self, value: &Exam, candidate: &(&Room, &DateTime<Utc>), plan: &Snapshot, is_check: bool) -> Result<(), Violation> {
+        (self.func)(value, candidate, plan, is_check).map_err(|conflict| Violation::new(self.severity, &self.rule[..], conflict))
+    }
+}
+
+#[derive(Clone)]
+pub struct SoftConstraint {
+    pub func: Arc<SoftFn>,
+}
+
+impl SoftConstraint {
+    pub fn new(func: Arc<SoftFn>) -> Self {
+        Self { func }
+    }
+}
+
+#[derive(Clone)]
+pub struct Constraints {
+    pub hard: Vec<HardConstraint>,
+    pub soft: Vec<SoftConstraint>,
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        RuleSet::default().build()
+    }
+}
+
+impl Constraints {
+    /// the first error of the candidate, warnings and infos are ignored.
+    /// the solver uses this to rule out candidates quickly
+    pub fn apply_hard(&self, value: &Exam, candidate: &(&Room, &DateTime<Utc>), plan: &Snapshot, is_check: bool) -> Result<(), Violation> {
+        match self.hard.iter().filter(|v| v.severity == Severity::Error).find_map(|v| v.check(value, candidate, plan, is_check).err()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// all violations of a booked exam, of every severity
+    pub fn violations(&self, value: &Exam, candidate: &(&Room, &DateTime<Utc>), plan: &Snapshot) -> Vec<Violation> {
+        self.hard.iter().filter_map(|v| v.check(value, candidate, plan, true).err()).collect()
+    }
+
+    pub fn apply_soft(&self, value: &Exam, candidate: &(&Room, &DateTime<Utc>), plan: &Snapshot) -> i32 {
+        self.soft.iter().fold(0, |acc, v| { acc + (v.func)(value, candidate, plan) })
+    }
+}
+
+/// a candidate with its score and a pseudo random tie-breaker, see [`tie_break`]
+struct Ranked<T>(i32, u64, T);
+
+/// an exam with the room and time it would be booked at
+pub type Candidate = (Id<Exam>, Id<Room>, DateTime<Utc>);
+
+/// an additional score for a candidate, it is called from several threads
+type Bonus<'a> = dyn Fn(&Exam, &Room, &DateTime<Utc>) -> i32 + Sync + 'a;
+
+/// settings of the solver that are saved with the plan, so a schedule can be reproduced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolverConfig {
+    /// decides between equally scored candidates
+    pub seed: u64,
+}
+
+/// ranks equally scored candidates by a hash of the seed, the exam uuid and id, the room number and the time.
+/// unlike the order of the stores these stay the same when a plan is saved and loaded again,
+/// the uuid keeps exams with empty or equal ids apart
+fn tie_break(seed: u64, exam: &Exam, room: &Room, start: &DateTime<Utc>) -> u64 {
+    // fnv-1a over the names, then the splitmix64 finalizer to spread similar inputs
+    let bytes = exam.uuid.as_bytes().iter().copied()
+        .chain(exam.id.bytes()).chain([0])
+        .chain(room.number.bytes()).chain([0])
+        .chain(start.timestamp().to_le_bytes());
+    let mut hash = bytes.fold(seed ^ 0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// keeps the better of two candidates, the one with the higher tie-breaker wins equal scores.
+/// if those are equal too the first one is kept, rayon reduces neighbouring items in order,
+/// so the result does not depend on the number of threads
+fn better<T>(first: Option<Ranked<T>>, second: Option<Ranked<T>>) -> Option<Ranked<T>> {
+    match (first, second) {
+        (Some(Ranked(a, a_tie, v)), Some(Ranked(b, b_tie, _))) if (a, a_tie) >= (b, b_tie) => Some(Ranked(a, a_tie, v)),
+        (None, second) => second,
+        (first, None) => first,
+        (_, second) => second,
+    }
+}
+
+pub struct SolveResult {
+    /// the seed of the [`SolverConfig`] the plan was solved with
+    pub seed: u64,
+    pub finished_exams: Vec<Id<Exam>>,
+    /// exams that were booked before and now are booked somewhere else
+    pub moved_exams: Vec<Id<Exam>>,
+    /// whether the solver was stopped before it was done
+    pub cancelled: bool,
+}
+
+/// the state of a running solve, reported after every booked exam
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub placed: usize,
+    pub remaining: usize,
+    /// the highest score of the candidates booked so far
+    pub best_score: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveMode {
+    /// see [`solve`]
+    Full,
+    /// see [`solve_incremental`]
+    Incremental,
+}
+
+/// the bonus of keeping an exam at its previous time, it outweighs every soft constraint
+const KEEP_TIME_BONUS: i32 = 1000;
+/// the additional bonus of keeping an exam in its previous room
+const KEEP_ROOM_BONUS: i32 = 500;
+
+/// the best placement of `exam` on `day`, `bonus` is added to the soft score of every candidate.
+/// the rooms are checked in parallel on the current rayon thread pool
+fn best_placement(plan: &Snapshot, exam: &Exam, timetable: &Timetable, day: Date<Utc>, constraints: &Constraints, seed: u64, bonus: &Bonus) -> Option<Ranked<(Id<Room>, DateTime<Utc>)>> {
+    plan.rooms.as_slice().par_iter()
+        .flat_map_iter(|room| timetable.times.iter().map(move |t| (room, day.and_time(t.start).unwrap())))
+        .filter(|(room, start)| constraints.apply_hard(exam, &(*room, start), plan, false).is_ok())
+        .map(|(room, start)| {
+            let score = constraints.apply_soft(exam, &(room, &start), plan) + bonus(exam, room, &start);
+            Some(Ranked(score, tie_break(seed, exam, room, &start), (room.id(), start)))
+        })
+    .reduce(|| None, better)
+}
+
+fn best_candidate_with(plan: &Snapshot, timetable: &Timetable, day: Date<Utc>, constraints: &Constraints, seed: u64, bonus: &Bonus) -> Option<Ranked<Candidate>> {
+    plan.unfinished_exams.as_slice().par_iter()
+        .map(|exam| {
+            best_placement(plan, exam, timetable, day, constraints, seed, bonus)
+                .map(|Ranked(score, tie, (room, start))| Ranked(score, tie, (exam.id(), room, start)))
+        })
+    .reduce(|| None, better)
+}
+
+/// the best placement of any unfinished exam on `day`, ties are broken by `seed`
+pub fn best_candidate(plan: &Snapshot, timetable: &Timetable, day: Date<Utc>, constraints: &Constraints, seed: u64) -> Option<Candidate> {
+    best_candidate_with(plan, timetable, day, constraints, seed, &|_, _, _| 0).map(|Ranked(_, _, found)| found)
+}
+
+/// books unfinished exams one after another until all are placed or none fits anymore.
+/// the candidates are evaluated on a snapshot of the plan, so nothing is locked
+pub fn solve(data: &mut PlanerData, day: Date<Utc>) -> Result<SolveResult, SolveResult> {
+    run(data, day, SolveMode::Full, &mut |_| true)
+}
+
+/// runs the solver in `mode`, `on_progress` is called after every step and stops the solver by
+/// returning `false`. the exams booked until then stay booked
+pub fn run(data: &mut PlanerData, day: Date<Utc>, mode: SolveMode, on_progress: &mut dyn FnMut(&Progress) -> bool) -> Result<SolveResult, SolveResult> {
+    match mode {
+        SolveMode::Full => {
+            let mut finished_exams = Vec::new();
+            let cancelled = !place_unfinished(data, day, &|_, _, _| 0, &mut finished_exams, on_progress);
+
+            let result = SolveResult { seed: data.solver.seed, finished_exams, moved_exams: Vec::new(), cancelled };
+            if data.unfinished_exams.is_empty() && !cancelled { Ok(result) } else { Err(result) }
+        },
+        SolveMode::Incremental => incremental(data, day, on_progress),
+    }
+}
+
+/// books the best candidates into `finished_exams`, returns `false` if it was cancelled
+fn place_unfinished(data: &mut PlanerData, day: Date<Utc>, bonus: &Bonus, finished_exams: &mut Vec<Id<Exam>>, on_progress: &mut dyn FnMut(&Progress) -> bool) -> bool {
+    let mut best_score = None;
+    while !data.unfinished_exams.is_empty() {
+        let found = best_candidate_with(&data.snapshot(), &data.timetable, day, &data.constraints, data.solver.seed, bonus);
+
+        match found {
+            Some(Ranked(score, _, (exam, room, start))) => {
+                data.book_exam(exam, room, start);
+                data.finish_exam(exam);
+                finished_exams.push(exam);
+                best_score = best_score.max(Some(score));
+
+                let progress = Progress { placed: finished_exams.len(), remaining: data.unfinished_exams.len(), best_score };
+                if !on_progress(&progress) { return false }
+            },
+            None => break,
+        }
+    }
+
+    true
+}
+
+/// like [`solve`], but the unpinned exams that are already booked on `day` may be moved to fix
+/// their conflicts or to make room for unfinished exams. pinned exams are never moved and
+/// as few bookings as possible are changed, so schedules that were already handed out stay valid
+pub fn solve_incremental(data: &mut PlanerData, day: Date<Utc>) -> Result<SolveResult, SolveResult> {
+    run(data, day, SolveMode::Incremental, &mut |_| true)
+}
+
+fn incremental(data: &mut PlanerData, day: Date<Utc>, on_progress: &mut dyn FnMut(&Progress) -> bool) -> Result<SolveResult, SolveResult> {
+    let before: HashMap<Id<Exam>, (Id<Room>, DateTime<Utc>)> = data.finished_exams.iter()
+        .filter_map(|exam| Some((exam.id(), exam.pairing?)))
+    .collect();
+
+    // unbook the unpinned exams on this day that break a hard constraint where they are
+    let plan = data.snapshot();
+    let conflicting: Vec<_> = data.finished_exams.iter()
+        .filter(|exam| !exam.pinned)
+        .filter(|exam| match exam.pairing {
+            Some((room, start)) if start.date() == day => match plan.rooms.get(room) {
+                Some(room) => data.constraints.apply_hard(exam, &(room, &start), &plan, true).is_err(),
+                None => true,
+            },
+            _ => false,
+        })
+        .map(|exam| exam.id())
+    .collect();
+
+    for exam in &conflicting {
+        data.unbook_exam(*exam);
+        data.unfinish_exam(*exam);
+    }
+
+    // exams that were booked before prefer their old time and room, conflicts between two exams
+    // are therefore usually solved by moving only one of them
+    let keep = |exam: &Exam, room: &Room, start: &DateTime<Utc>| match before.get(&exam.id()) {
+        Some((old_room, old_start)) if old_start == start => KEEP_TIME_BONUS + if *old_room == room.id() { KEEP_ROOM_BONUS } else { 0 },
+        _ => 0,
+    };
+    let mut finished_exams = Vec::new();
+    let mut cancelled = !place_unfinished(data, day, &keep, &mut finished_exams, on_progress);
+
+    // make room for the exams that still do not fit by moving a single unpinned exam each
+    let unplaced: Vec<_> = if cancelled { Vec::new() } else { data.unfinished_exams.ids().collect() };
+    for exam in unplaced {
+        let movable: Vec<_> = data.finished_exams.iter()
+            .filter(|v| !v.pinned)
+            .filter_map(|v| Some((v.id(), v.pairing?)))
+            .filter(|(_, (_, start))| start.date() == day)
+        .collect();
+
+        for (other, (old_room, old_start)) in movable {
+            data.unbook_exam(other);
+            data.unfinish_exam(other);
+
+            if try_place(data, exam, day, &keep) {
+                if try_place(data, other, day, &keep) {
+                    finished_exams.push(exam);
+                    break;
+                }
+
+                data.unbook_exam(exam);
+                data.unfinish_exam(exam);
+            }
+
+            data.book_exam(other, old_room, old_start);
+            data.finish_exam(other);
+        }
+
+        let progress = Progress { placed: finished_exams.len(), remaining: data.unfinished_exams.len(), best_score: None };
+        if !on_progress(&progress) {
+            cancelled = true;
+            break;
+        }
+    }
+
+    let moved_exams = data.finished_exams.iter()
+        .filter(|exam| matches!(before.get(&exam.id()), Some(old) if Some(*old) != exam.pairing))
+        .map(|exam| exam.id())
+    .collect();
+
+    let result = SolveResult { seed: data.solver.seed, finished_exams, moved_exams, cancelled };
+    if data.unfinished_exams.is_empty() && !cancelled { Ok(result) } else { Err(result) }
+}
+
+/// books a single unfinished exam at its best placement on `day`
+fn try_place(data: &mut PlanerData, exam: Id<Exam>, day: Date<Utc>, bonus: &Bonus) -> bool {
+    let plan = data.snapshot();
+    let found = match plan.unfinished_exams.get(exam) {
+        Some(v) => best_placement(&plan, v, &data.timetable, day, &data.constraints, data.solver.seed, bonus),
+        None => None,
+    };
+
+    match found {
+        Some(Ranked(_, _, (room, start))) => {
+            data.book_exam(exam, room, start);
+            data.finish_exam(exam);
+            true
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn back_to_back_exams_respect_buffers() {
+        let mut data = PlanerData::default();
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let teacher = data.add_teacher("Falk".to_owned(), "Eertel".to_owned(), None, None, &[]);
+        let start = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+
+        let exams: Vec<_> = (0..2).map(|i| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(teacher);
+            data.book_exam(exam, room, start + Duration::minutes(30 * i));
+            data.finish_exam(exam);
+            exam
+        }).collect();
+
+        data.compute_conflicts();
+        assert!(data.finished_exams.iter().all(|v| v.violations.is_empty()), "an exam ending at the start of another should not conflict");
+
+        data.rooms[room].buffer = Duration::minutes(5);
+        data.compute_conflicts();
+        let error = data.finished_exams[exams[1]].error_message().unwrap_or_default();
+        assert!(error.contains("room A1.03"), "the room buffer should be kept free, got {error:?}");
+
+        data.rooms[room].buffer = Duration::zero();
+        data.teachers[teacher].buffer = Duration::minutes(5);
+        data.compute_conflicts();
+        let error = data.finished_exams[exams[1]].error_message().unwrap_or_default();
+        assert!(error.contains("eng-0"), "the teacher buffer should be kept free, got {error:?}");
+    }
+
+    #[test]
+    fn incremental_solve_moves_few_exams() {
+        let day = Utc.ymd(2022, 12, 15);
+        let mut data = PlanerData::default();
+        data.timetable.times.truncate(4);
+        let rooms = [data.add_room("A1.03".to_owned(), Vec::new()), data.add_room("A1.04".to_owned(), Vec::new())];
+        let teachers: Vec<_> = (0..4).map(|i| data.add_teacher(format!("T{i}"), format!("Teacher{i}"), None, None, &[])).collect();
+
+        let add = |data: &mut PlanerData, i: usize, teacher| {
+            let exam = data.add_exam(format!("exam-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(teacher);
+            exam
+        };
+
+        let lessons: Vec<_> = data.timetable.times.iter().map(|v| day.and_time(v.start).unwrap()).collect();
+        let mut booked = Vec::new();
+        for (i, teacher) in teachers.iter().enumerate() {
+            let exam = add(&mut data, i, *teacher);
+            data.book_exam(exam, rooms[0], lessons[i]);
+            data.finish_exam(exam);
+            booked.push(exam);
+        }
+
+        // a manual change puts two exams of the same teacher at the same time in different rooms
+        let pinned = add(&mut data, 4, teachers[1]);
+        data.book_exam(pinned, rooms[1], lessons[1]);
+        data.finish_exam(pinned);
+        data.finished_exams[pinned].pinned = true;
+        let new = add(&mut data, 5, teachers[2]);
+        data.compute_conflicts();
+        assert!(data.finished_exams[booked[1]].has_errors());
+
+        let res = match solve_incremental(&mut data, day) {
+            Ok(v) => v,
+            Err(_) => panic!("all exams should be placed"),
+        };
+        data.compute_conflicts();
+
+        assert!(data.finished_exams.iter().all(|v| !v.has_errors()), "the conflicts should be solved");
+        assert_eq!(res.moved_exams, vec![booked[1]], "only the exam that conflicts with the pinned one should move");
+        assert_eq!(data.finished_exams[pinned].pairing, Some((rooms[1], lessons[1])), "pinned exams should stay");
+        assert!(data.finished_exams.contains(new));
+    }
+
+    #[test]
+    fn background_solve_applies_only_unedited_plans() {
+        let day = Utc.ymd(2022, 12, 15);
+        let mut data = PlanerData::default();
+        data.add_room("A1.03".to_owned(), Vec::new());
+        let exams: Vec<_> = (0..3).map(|i| data.add_exam(format!("exam-{i}"), Duration::minutes(30), Vec::new(), Vec::new())).collect();
+
+        let mut copy = data.clone();
+        let res = run(&mut copy, day, SolveMode::Full, &mut |_| false);
+        assert!(matches!(res, Err(SolveResult { cancelled: true, .. })));
+        assert_eq!(copy.finished_exams.len(), 1, "the solver should stop after the first step");
+
+        let mut scores = Vec::new();
+        assert!(run(&mut data.clone(), day, SolveMode::Full, &mut |p| { scores.push(p.best_score); true }).is_ok());
+        assert_eq!(scores.len(), 3);
+        assert!(scores[0].is_some() && scores.windows(2).all(|v| v[0] <= v[1]), "the best score should never drop");
+
+        let solving = background::BackgroundSolve::start(&data, day, SolveMode::Full).unwrap();
+        data.add_room("A1.04".to_owned(), Vec::new());
+        assert!(matches!(solving.finish(&mut data), background::Outcome::Discarded));
+        assert_eq!(data.unfinished_exams.len(), 3, "an edited plan should not be overwritten");
+
+        let solving = background::BackgroundSolve::start(&data, day, SolveMode::Full).unwrap();
+        match solving.finish(&mut data) {
+            background::Outcome::Applied { result: Ok(_), previous } => assert_eq!(previous.label, "compute"),
+            _ => panic!("the result should be applied"),
+        }
+        assert!(exams.iter().all(|v| data.finished_exams.contains(*v)));
+    }
+
+    #[test]
+    fn parallel_solve_is_deterministic() {
+        let day = Utc.ymd(2022, 12, 15);
+        let mut data = PlanerData::default();
+        for i in 0..4 {
+            data.add_room(format!("A1.0{i}"), Vec::new());
+        }
+        let teachers: Vec<_> = (0..3).map(|i| data.add_teacher(format!("T{i}"), format!("Teacher{i}"), None, None, &[])).collect();
+        for i in 0..12 {
+            let exam = data.add_exam(format!("exam-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.unfinished_exams[exam].examiners[0] = Some(teachers[i % teachers.len()]);
+        }
+
+        // every room and lesson scores the same, so only the tie-breaking decides
+        let solve_with = |threads| {
+            let mut data = data.clone();
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            assert!(pool.install(|| solve(&mut data, day)).is_ok());
+            data.finished_exams.iter().map(|v| (v.id(), v.pairing)).collect::<Vec<_>>()
+        };
+
+        let single = solve_with(1);
+        for threads in [2, 4, 8] {
+            assert_eq!(solve_with(threads), single, "{threads} threads should give the same plan as one");
+        }
+    }
+
+    fn seeded_plan(seed: u64, reversed: bool) -> Vec<(String, String, String)> {
+        let day = Utc.ymd(2022, 12, 15);
+        let mut data = PlanerData::default();
+        data.solver.seed = seed;
+        data.timetable.times.truncate(3);
+
+        let mut rooms: Vec<_> = (0..3).map(|i| format!("A1.0{i}")).collect();
+        let mut exams: Vec<_> = (0..4).collect();
+        if reversed {
+            rooms.reverse();
+            exams.reverse();
+        }
+        for room in rooms {
+            data.add_room(room, Vec::new());
+        }
+        // the uuids take part in the tie-breaking, so they are fixed like in a loaded plan
+        for i in exams {
+            let id = data.add_exam(format!("exam-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            let mut exam = data.unfinished_exams.remove(id).unwrap();
+            exam.uuid = uuid::Uuid::from_u128(i as u128 + 1);
+            data.unfinished_exams.insert(exam);
+        }
+
+        assert!(matches!(solve(&mut data, day), Ok(SolveResult { seed: v, .. }) if v == seed));
+
+        let mut plan: Vec<_> = data.finished_exams.iter()
+            .map(|exam| {
+                let (room, start) = exam.pairing.unwrap();
+                (exam.id.clone(), data.rooms[room].number.clone(), start.format("%H:%M").to_string())
+            })
+        .collect();
+        plan.sort();
+        plan
+    }
+
+    #[test]
+    fn seeded_solve_is_reproducible() {
+        let golden: Vec<_> = [
+            ("exam-0", "A1.02", "09:40"),
+            ("exam-1", "A1.02", "08:50"),
+            ("exam-2", "A1.02", "08:00"),
+            ("exam-3", "A1.01", "08:50"),
+        ].iter().map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string())).collect();
+
+        assert_eq!(seeded_plan(42, false), golden);
+        assert_eq!(seeded_plan(42, true), golden, "the order of the exams and rooms should not matter");
+        assert_ne!(seeded_plan(7, false), golden, "another seed should break the ties differently");
+    }
+}
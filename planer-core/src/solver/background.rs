@@ -0,0 +1,99 @@
+use std::{
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::prelude::*;
+
+use crate::planer::{PlanerData, Checkpoint};
+
+use super::{run, Progress, SolveMode, SolveResult};
+
+/// a solver running on its own thread over a copy of the plan, so the ui stays responsive
+pub struct BackgroundSolve {
+    handle: JoinHandle<(PlanerData, Result<SolveResult, SolveResult>)>,
+    progress: Arc<Mutex<Progress>>,
+    cancel: Arc<AtomicBool>,
+    started: Instant,
+    /// the plan the solver started from, the result is only applied if it did not change
+    base: Checkpoint,
+}
+
+pub enum Outcome {
+    /// the result was applied, `previous` is the plan from before
+    Applied { result: Result<SolveResult, SolveResult>, previous: Checkpoint },
+    /// the plan was edited while solving, so the result was thrown away
+    Discarded,
+    Cancelled,
+    Failed(String),
+}
+
+impl BackgroundSolve {
+    pub fn start(data: &PlanerData, day: Date<Utc>, mode: SolveMode) -> Result<Self, String> {
+        let label = match mode {
+            SolveMode::Full => "compute",
+            SolveMode::Incremental => "re-solve",
+        };
+        let base = data.checkpoint(label)?;
+
+        let progress = Arc::new(Mutex::new(Progress { remaining: data.unfinished_exams.len(), ..Default::default() }));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let mut data = data.clone();
+        let (thread_progress, thread_cancel) = (progress.clone(), cancel.clone());
+        let handle = thread::Builder::new()
+            .name("solver".to_owned())
+            .spawn(move || {
+                let result = run(&mut data, day, mode, &mut |v| {
+                    if let Ok(mut progress) = thread_progress.lock() {
+                        *progress = v.clone();
+                    }
+                    !thread_cancel.load(Ordering::Relaxed)
+                });
+                (data, result)
+            })
+            .map_err(|err| format!("could not start the solver: {err}"))?;
+
+        Ok(Self { handle, progress, cancel, started: Instant::now(), base })
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.progress.lock().map(|v| v.clone()).unwrap_or_default()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// asks the solver to stop after the current step
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// waits for the solver and applies its result to `data` in one step
+    pub fn finish(self, data: &mut PlanerData) -> Outcome {
+        let cancelled = self.is_cancelled();
+        let (solved, result) = match self.handle.join() {
+            Ok(v) => v,
+            Err(_) => return Outcome::Failed("the solver crashed".to_owned()),
+        };
+
+        if cancelled {
+            Outcome::Cancelled
+        } else if !data.is_at(&self.base) {
+            Outcome::Discarded
+        } else {
+            data.replace_plan(solved);
+            Outcome::Applied { result, previous: self.base }
+        }
+    }
+}
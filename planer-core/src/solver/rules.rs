@@ -0,0 +1,332 @@
+use std::sync::Arc;
+
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+
+use crate::planer::{store::AsUuid, violation::{Conflict, Entity, Severity}};
+
+use super::{Constraints, HardConstraint, SoftConstraint, script::CompiledScript};
+
+macro_rules! constraint {
+    (hard: $rule:expr, $fn:expr) => {
+        HardConstraint::new($rule.kind.name(), $rule.severity, Arc::new($fn))
+    };
+    (soft: $fn:expr) => {
+        SoftConstraint::new(Arc::new($fn))
+    };
+}
+
+/// the built-in constraints, they are saved with the plan as data and turned into closures by [`RuleSet::build`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum RuleKind {
+    /// a room holds one exam at a time, including its changeover buffer
+    RoomBooked,
+    /// the room has every tag the exam requires
+    RequiredTags,
+    /// examiners and examinees attend one exam at a time, including their changeover buffers
+    PeopleBooked,
+    /// the room and the examiners are not blocked (e.g. by an imported absence)
+    Blocked,
+    /// a student has at most `max` exams on a day
+    StudentExamsPerDay { max: usize },
+    /// the exam has at least one examiner
+    HasExaminer,
+    /// rooms with matching tags are ranked higher, `required` and `optional` are the points per tag
+    MatchingTags { required: i32, optional: i32 },
+    /// earlier lessons are ranked higher, so the afternoons stay free
+    PreferEarly,
+    /// a custom constraint written by the user, see [`CompiledScript`]
+    Script { name: String, hard: bool, source: String },
+}
+
+impl RuleKind {
+    pub fn is_hard(&self) -> bool {
+        match self {
+            RuleKind::Script { hard, .. } => *hard,
+            _ => !matches!(self, RuleKind::MatchingTags { .. } | RuleKind::PreferEarly),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            RuleKind::RoomBooked => "room is free",
+            RuleKind::RequiredTags => "room has the required tags",
+            RuleKind::PeopleBooked => "people are free",
+            RuleKind::Blocked => "no blocked times",
+            RuleKind::StudentExamsPerDay { .. } => "exams per student and day",
+            RuleKind::HasExaminer => "exam has an examiner",
+            RuleKind::MatchingTags { .. } => "prefer matching tags",
+            RuleKind::PreferEarly => "prefer early lessons",
+            RuleKind::Script { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(flatten)]
+    pub kind: RuleKind,
+    pub enabled: bool,
+    /// the score of soft rules is multiplied by it, hard rules ignore it
+    pub weight: i32,
+    /// how violations of hard rules are reported, only errors are avoided by the solver
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+impl Rule {
+    pub fn new(kind: RuleKind) -> Self {
+        Self { kind, enabled: true, weight: 1, severity: Severity::Error }
+    }
+
+    /// the syntax error of a script rule
+    pub fn script_error(&self) -> Option<String> {
+        match &self.kind {
+            RuleKind::Script { source, .. } => CompiledScript::compile(source).err(),
+            _ => None,
+        }
+    }
+
+    /// scripts that do not compile are left out, the editor shows their error
+    fn hard(&self) -> Option<HardConstraint> {
+        Some(match self.kind {
+            // check if the room is already booked, including its changeover buffer
+            RuleKind::RoomBooked => constraint!(hard: self, |exam, (room, start), _plan, _is_check| {
+                let booked: Vec<_> = room.calendar.get_booked_with_buffer(start, exam.duration, room.buffer).iter()
+                    .filter(|b| b.data.uuid() != exam.uuid)
+                    .map(|b| Entity::Exam(b.data))
+                .collect();
+
+                if !booked.is_empty() {
+                    let involved = [Entity::Room(room.id())].into_iter().chain(booked).collect();
+                    Err(Conflict::new(format!("the room {} is already booked at {}", room.number, start), involved))
+                } else { Ok(()) }
+            }),
+
+            RuleKind::RequiredTags => constraint!(hard: self, |exam, (room, _start), _plan, _is_check| {
+                let missing: Vec<_> = exam.tags.iter()
+                    .filter_map(|tag| if tag.required && !room.tags.contains(&tag.name) {
+                        Some(format!("\n - {}", tag.name.clone()))
+                    } else { None })
+                .collect();
+
+                if missing.is_empty() {
+                    Ok(())
+                } else {
+                    let missing = missing.join("");
+                    Err(Conflict::new(format!("the following required tags are missing from the room:{missing}"), vec![Entity::Room(room.id())]))
+                }
+            }),
+
+            // check if a participant is already booked, including their changeover buffer
+            RuleKind::PeopleBooked => constraint!(hard: self, |exam, (_room, start), plan, _is_check| {
+                let examiners = exam.examiners.iter()
+                    .filter_map(|examiner| plan.teachers.get((*examiner)?))
+                    .map(|v| (Entity::Teacher(v.id()), &v.name, &v.calendar, v.buffer));
+                let examinees = exam.examinees.iter()
+                    .filter_map(|examinee| plan.students.get(*examinee))
+                    .map(|v| (Entity::Student(v.id()), &v.name, &v.calendar, v.buffer));
+
+                let mut involved = Vec::new();
+                let booked: Vec<_> = examiners.chain(examinees)
+                    .filter_map(|(person, name, calendar, buffer)| {
+                        let bookings = calendar.get_booked_with_buffer(start, exam.duration, buffer).iter()
+                            .filter(|b| b.data.uuid() != exam.uuid)
+                            .filter_map(|b| plan.exam(b.data))
+                        .collect::<Vec<_>>();
+
+                        if !bookings.is_empty() {
+                            involved.push(person);
+                            involved.extend(bookings.iter().map(|v| Entity::Exam(v.id())));
+                            let ids: Vec<_> = bookings.iter().map(|v| v.id.clone()).collect();
+                            Some(format!("\n{}: {}", name, ids.join(", ")))
+                        } else { None }
+                    })
+                .collect();
+
+                if !booked.is_empty() {
+                    Err(Conflict::new(format!("the following people are already booked:{}", booked.join("")), involved))
+                } else { Ok(()) }
+            }),
+
+            // check if the room or an examiner is blocked (e.g. by an imported absence)
+            RuleKind::Blocked => constraint!(hard: self, |exam, (room, start), plan, _is_check| {
+                let mut involved = Vec::new();
+                let mut blocked: Vec<_> = room.blocked.get_booked_from_to(start, exam.duration).iter()
+                    .map(|v| format!("\nroom {}: {}", room.number, v.data.summary))
+                .collect();
+                if !blocked.is_empty() {
+                    involved.push(Entity::Room(room.id()));
+                }
+
+                for examiner in exam.examiners.iter().filter_map(|examiner| plan.teachers.get((*examiner)?)) {
+                    let before = blocked.len();
+                    blocked.extend(examiner.blocked.get_booked_from_to(start, exam.duration).iter()
+                        .map(|v| format!("\n{}: {}", examiner.name, v.data.summary)));
+                    if blocked.len() != before {
+                        involved.push(Entity::Teacher(examiner.id()));
+                    }
+                }
+
+                if !blocked.is_empty() {
+                    Err(Conflict::new(format!("the following are not available:{}", blocked.join("")), involved))
+                } else { Ok(()) }
+            }),
+
+            RuleKind::StudentExamsPerDay { max } => constraint!(hard: self, move |exam, (_room, start), plan, _is_check| {
+                let day = start.date().and_hms(0, 0, 0);
+                let busy: Vec<_> = exam.examinees.iter()
+                    .filter_map(|examinee| plan.students.get(*examinee))
+                    .filter(|student| {
+                        let others = student.calendar.get_booked_from_to(&day, Duration::days(1)).iter()
+                            .filter(|b| b.data.uuid() != exam.uuid)
+                        .count();
+                        others + 1 > max
+                    })
+                .collect();
+
+                if !busy.is_empty() {
+                    let names: Vec<_> = busy.iter().map(|student| format!("\n{}", student.name)).collect();
+                    let involved = busy.iter().map(|student| Entity::Student(student.id())).collect();
+                    Err(Conflict::new(format!("the following students would have more than {max} exams on {}:{}", start.format("%d.%m.%Y"), names.join("")), involved))
+                } else { Ok(()) }
+            }),
+
+            RuleKind::HasExaminer => constraint!(hard: self, |exam, _candidate, _plan, _is_check| {
+                if exam.examiners.iter().any(Option::is_some) {
+                    Ok(())
+                } else {
+                    Err(Conflict::new("the exam has no examiner", Vec::new()))
+                }
+            }),
+
+            RuleKind::Script { ref name, hard: true, ref source } => {
+                let script = CompiledScript::compile(source).ok()?;
+                let name = name.clone();
+
+                constraint!(hard: self, move |exam, (room, start), plan, _is_check| {
+                    match script.eval_hard(exam, room, start, plan) {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(reason)) if reason.is_empty() => Err(Conflict::new(format!("not allowed by {name}"), vec![Entity::Room(room.id())])),
+                        Ok(Err(reason)) => Err(Conflict::new(format!("{name}: {reason}"), vec![Entity::Room(room.id())])),
+                        Err(err) => Err(Conflict::new(format!("the script {name} failed: {err}"), Vec::new())),
+                    }
+                })
+            },
+
+            RuleKind::MatchingTags { .. } | RuleKind::PreferEarly | RuleKind::Script { .. } => return None,
+        })
+    }
+
+    fn soft(&self) -> Option<SoftConstraint> {
+        let weight = self.weight;
+
+        Some(match self.kind {
+            // rank rooms with matching tags heigher
+            RuleKind::MatchingTags { required, optional } => constraint!(soft: move |exam, (room, _start), _plan| {
+                exam.tags.iter()
+                    .filter_map(|tag| {
+                        if room.tags.contains(&tag.name) {
+                            Some(if tag.required { required } else { optional })
+                        } else { None }
+                    })
+                .sum::<i32>() * weight
+            }),
+
+            // one point less for every hour after midnight
+            RuleKind::PreferEarly => constraint!(soft: move |_exam, (_room, start), _plan| {
+                -(start.hour() as i32) * weight
+            }),
+
+            RuleKind::Script { hard: false, ref source, .. } => {
+                let script = CompiledScript::compile(source).ok()?;
+
+                // a failing script does not change the ranking, the editor shows the error
+                constraint!(soft: move |exam, (room, start), plan| {
+                    script.eval_soft(exam, room, start, plan).unwrap_or(0) * weight
+                })
+            },
+
+            _ => return None,
+        })
+    }
+}
+
+/// the configurable constraints of a plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        let disabled = |kind| Rule { enabled: false, ..Rule::new(kind) };
+
+        Self {
+            rules: vec![
+                Rule::new(RuleKind::RoomBooked),
+                Rule::new(RuleKind::RequiredTags),
+                Rule::new(RuleKind::PeopleBooked),
+                Rule::new(RuleKind::Blocked),
+                disabled(RuleKind::StudentExamsPerDay { max: 2 }),
+                Rule { severity: Severity::Warning, ..Rule::new(RuleKind::HasExaminer) },
+                Rule::new(RuleKind::MatchingTags { required: 2, optional: 1 }),
+                disabled(RuleKind::PreferEarly),
+            ],
+        }
+    }
+}
+
+impl RuleSet {
+    /// builds the constraints of the enabled rules
+    pub fn build(&self) -> Constraints {
+        let enabled = || self.rules.iter().filter(|v| v.enabled);
+
+        Constraints {
+            hard: enabled().filter_map(Rule::hard).collect(),
+            soft: enabled().filter_map(Rule::soft).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::planer::PlanerData;
+
+    use super::*;
+
+    #[test]
+    fn rules_are_saved_and_applied() {
+        let mut data = PlanerData::default();
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let start = Utc.ymd(2022, 12, 15).and_hms(8, 0, 0);
+        let exams: Vec<_> = (0..2).map(|i| {
+            let exam = data.add_exam(format!("eng-{i}"), Duration::minutes(30), Vec::new(), Vec::new());
+            data.book_exam(exam, room, start);
+            data.finish_exam(exam);
+            exam
+        }).collect();
+
+        data.compute_conflicts();
+        assert!(data.finished_exams[exams[1]].has_errors(), "the room is booked twice");
+
+        for rule in &mut data.rules.rules {
+            match &mut rule.kind {
+                RuleKind::RoomBooked => rule.enabled = false,
+                RuleKind::MatchingTags { required, .. } => *required = 5,
+                _ => (),
+            }
+        }
+        data.apply_rules();
+        assert!(data.finished_exams.iter().all(|v| !v.has_errors()), "disabled rules should not be checked");
+
+        let json = serde_json::to_string(&data).unwrap();
+        let loaded: PlanerData = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.rules, data.rules);
+
+        let old: PlanerData = serde_json::from_str(&json.replace("\"rules\":", "\"old_rules\":")).unwrap();
+        assert_eq!(old.rules, RuleSet::default(), "plans without rules should get the built-in ones");
+    }
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use chrono::prelude::*;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::planer::{Exam, Room, Snapshot, PlanerData};
+
+/// a script stops with an error after this many operations, so an endless loop can not hang the solver
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// the script of a custom constraint, written in [rhai](https://rhai.rs).
+///
+/// scripts can read `exam` (`id`, `duration` in minutes, `subjects`, `tags`, `required_tags`,
+/// `examiners` as shorthands and `examinees` as the number of students), `room` (`number`, `tags`)
+/// and `start` (`year`, `month`, `day`, `weekday` from 1 for monday, `hour`, `minute`).
+/// hard scripts return `true` if the candidate is allowed, `false` or a reason if it is not.
+/// soft scripts return a score
+#[derive(Clone)]
+pub struct CompiledScript {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl CompiledScript {
+    /// compiles `source`, the error contains the line and column of a syntax error
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let ast = engine.compile(source).map_err(|err| err.to_string())?;
+        Ok(Self { engine: Arc::new(engine), ast: Arc::new(ast) })
+    }
+
+    fn eval(&self, exam: &Exam, room: &Room, start: &DateTime<Utc>, plan: &Snapshot) -> Result<Dynamic, String> {
+        let mut scope = Scope::new();
+        scope.push_constant("exam", exam_map(exam, plan));
+        scope.push_constant("room", room_map(room));
+        scope.push_constant("start", time_map(start));
+
+        self.engine.eval_ast_with_scope(&mut scope, &self.ast).map_err(|err| err.to_string())
+    }
+
+    /// the outer error is a runtime error of the script, the inner one the reason the candidate is not allowed
+    pub fn eval_hard(&self, exam: &Exam, room: &Room, start: &DateTime<Utc>, plan: &Snapshot) -> Result<Result<(), String>, String> {
+        let res = self.eval(exam, room, start, plan)?;
+
+        if let Ok(allowed) = res.as_bool() {
+            Ok(if allowed { Ok(()) } else { Err(String::new()) })
+        } else if res.is_string() {
+            Ok(Err(res.into_string()?))
+        } else if res.is_unit() {
+            Ok(Ok(()))
+        } else {
+            Err(format!("a hard constraint has to return a bool or a string, not {}", res.type_name()))
+        }
+    }
+
+    pub fn eval_soft(&self, exam: &Exam, room: &Room, start: &DateTime<Utc>, plan: &Snapshot) -> Result<i32, String> {
+        let res = self.eval(exam, room, start, plan)?;
+
+        if let Ok(v) = res.as_int() {
+            Ok(v.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        } else if let Ok(v) = res.as_float() {
+            Ok(v.round() as i32)
+        } else {
+            Err(format!("a soft constraint has to return a number, not {}", res.type_name()))
+        }
+    }
+
+    /// runs the script on every booked exam of `data` and describes the results,
+    /// so runtime errors show up while the script is edited
+    pub fn test(&self, hard: bool, data: &PlanerData) -> Result<String, String> {
+        let plan = data.snapshot();
+        let booked: Vec<_> = data.finished_exams.iter()
+            .filter_map(|exam| {
+                let (room, start) = exam.pairing?;
+                Some((exam, plan.rooms.get(room)?, start))
+            })
+        .collect();
+
+        if hard {
+            let mut rejected = 0;
+            for (exam, room, start) in &booked {
+                if self.eval_hard(exam, room, start, &plan).map_err(|err| format!("{}: {err}", exam.id))?.is_err() {
+                    rejected += 1;
+                }
+            }
+            Ok(format!("rejects {rejected} of {} booked exams", booked.len()))
+        } else {
+            let mut scores = Vec::new();
+            for (exam, room, start) in &booked {
+                scores.push(self.eval_soft(exam, room, start, &plan).map_err(|err| format!("{}: {err}", exam.id))?);
+            }
+            match (scores.iter().min(), scores.iter().max()) {
+                (Some(min), Some(max)) => Ok(format!("scores the {} booked exams from {min} to {max}", scores.len())),
+                _ => Ok("there are no booked exams to test on".to_owned()),
+            }
+        }
+    }
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a String>) -> Dynamic {
+    values.cloned().map(Dynamic::from).collect::<Array>().into()
+}
+
+fn exam_map(exam: &Exam, plan: &Snapshot) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("id".into(), exam.id.clone().into());
+    map.insert("duration".into(), exam.duration.num_minutes().into());
+    map.insert("subjects".into(), strings(exam.subjects.iter()));
+    map.insert("tags".into(), strings(exam.tags.iter().map(|v| &v.name)));
+    map.insert("required_tags".into(), strings(exam.tags.iter().filter(|v| v.required).map(|v| &v.name)));
+    map.insert("examiners".into(), strings(exam.examiners.iter()
+        .filter_map(|examiner| plan.teachers.get((*examiner)?))
+        .map(|v| &v.shorthand)));
+    map.insert("examinees".into(), (exam.examinees.len() as i64).into());
+    map.into()
+}
+
+fn room_map(room: &Room) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("number".into(), room.number.clone().into());
+    map.insert("tags".into(), strings(room.tags.iter()));
+    map.into()
+}
+
+fn time_map(start: &DateTime<Utc>) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("year".into(), (start.year() as i64).into());
+    map.insert("month".into(), (start.month() as i64).into());
+    map.insert("day".into(), (start.day() as i64).into());
+    map.insert("weekday".into(), (start.weekday().number_from_monday() as i64).into());
+    map.insert("hour".into(), (start.hour() as i64).into());
+    map.insert("minute".into(), (start.minute() as i64).into());
+    map.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::{planer::Tag, solver::rules::{Rule, RuleKind}};
+
+    use super::*;
+
+    #[test]
+    fn scripted_constraints() {
+        let monday = Utc.ymd(2022, 12, 12);
+        let mut data = PlanerData::default();
+        let room = data.add_room("A1.03".to_owned(), Vec::new());
+        let piano = data.add_room("M0.01".to_owned(), vec!["piano".to_owned()]);
+        let latin = data.add_exam("lat-1".to_owned(), Duration::minutes(30), vec!["Latin".to_owned()], Vec::new());
+        let music = data.add_exam("mus-1".to_owned(), Duration::minutes(30), vec!["Music".to_owned()], vec![Tag { name: "piano".to_owned(), required: false }]);
+
+        assert!(CompiledScript::compile("exam.id ==").is_err(), "syntax errors should be reported");
+
+        let no_latin = r#"!(exam.subjects.contains("Latin") && start.weekday == 1)"#;
+        let piano_rooms = r#"if exam.subjects.contains("Music") && !(room.tags.contains("piano") && start.hour >= 13) { "music only in piano rooms after 13:00" } else { true }"#;
+        for (name, source) in [("no latin on monday", no_latin), ("piano", piano_rooms)] {
+            data.rules.rules.push(Rule::new(RuleKind::Script { name: name.to_owned(), hard: true, source: source.to_owned() }));
+        }
+        data.apply_rules();
+
+        let plan = data.snapshot();
+        let check = |exam, room, start: DateTime<Utc>| data.constraints.apply_hard(&plan.unfinished_exams[exam], &(&plan.rooms[room], &start), &plan, false).map_err(|v| v.message);
+        assert_eq!(check(latin, room, monday.and_hms(8, 0, 0)), Err("not allowed by no latin on monday".to_owned()));
+        assert!(check(latin, room, monday.succ().and_hms(8, 0, 0)).is_ok());
+        assert_eq!(check(music, piano, monday.and_hms(8, 0, 0)), Err("piano: music only in piano rooms after 13:00".to_owned()));
+        assert_eq!(check(music, room, monday.and_hms(14, 0, 0)), Err("piano: music only in piano rooms after 13:00".to_owned()));
+        assert!(check(music, piano, monday.and_hms(14, 0, 0)).is_ok());
+
+        let endless = CompiledScript::compile("loop {}").unwrap();
+        let err = endless.eval_hard(&plan.unfinished_exams[music], &plan.rooms[room], &monday.and_hms(8, 0, 0), &plan);
+        assert!(err.is_err(), "scripts should not run forever");
+    }
+}
@@ -1,10 +1,13 @@
-use std::{cell::RefCell, sync::Mutex};
+use std::cell::RefCell;
 
-use chrono::{Duration, Utc};
+use chrono::{Date, Datelike, Duration, Utc};
+use chrono_tz::{Tz, TZ_VARIANTS};
 use eframe::{egui::{self, emath}, epaint::{vec2, pos2}};
 use uuid::Uuid;
 
-use crate::{drag_and_drop::drop_target, planer::{PlanerData, Exam, Teacher, Student, uuid_ref::UuidRef, Tag, Name, calendar::Event}, modal::Modal, search::SearchData};
+use planer_core::{planer::{PlanerData, Exam, Room, Teacher, Student, store::{Id, Store, AsUuid}, Tag, Name, calendar::{Event, Calendar, TimeRange}, default_shorthand, BlockedTime, ClearScope, Checkpoint, violation::Severity}, import::{self, ImportReport}, export::{self, ics, html::HtmlOptions, pdf::PdfLayout, sheet::SheetFormat}, solver::{SolveMode, background::{BackgroundSolve, Outcome}, rules::{Rule, RuleKind, RuleSet}, script::CompiledScript}};
+
+use crate::{drag_and_drop::{drop_target, highlighted_drop_target, dragged}, modal::Modal, search::SearchData, conflicts::{ConflictPanel, severity_icon}, schedule::{TeacherSchedule, StudentSchedule}, date_picker::date_picker};
 
 use super::drag_and_drop::drag_source;
 
@@ -19,6 +22,24 @@ pub struct PlanerApp {
 
     search_data: SearchData<SearchType>,
     dummy_string: String,
+
+    import_report: Option<Result<ImportReport, String>>,
+    error_message: Option<String>,
+
+    /// destructive actions that can be undone, the last one is undone first
+    undo: Vec<UndoStep>,
+    solving: Option<BackgroundSolve>,
+
+    conflicts: ConflictPanel,
+    teacher_schedule: TeacherSchedule,
+    student_schedule: StudentSchedule,
+    /// the day shown in the calendar, the week views show the week of it
+    day: Date<Utc>,
+    view: CalendarView,
+    hidden_rooms: Vec<Id<Room>>,
+    /// the exam highlighted in the calendar, it is scrolled into view once if `scroll_to_focus` is set
+    focus: Option<Id<Exam>>,
+    scroll_to_focus: bool,
 }
 
 enum ExamView {
@@ -97,11 +118,28 @@ impl PlanerApp {
                 ("#", Tag),
             ]),
             dummy_string: "bio-2".to_string(),
+
+            import_report: None,
+            error_message: None,
+
+            undo: Vec::new(),
+            solving: None,
+
+            conflicts: ConflictPanel::new(),
+            teacher_schedule: TeacherSchedule::new(),
+            student_schedule: StudentSchedule::new(),
+            day: Utc::today(),
+            view: CalendarView::Day,
+            hidden_rooms: Vec::new(),
+            focus: None,
+            scroll_to_focus: false,
         }
     }
 
     pub fn new_plan(&mut self) {
+        self.stop_solving();
         self.data = PlanerData::default();
+        self.undo.clear();
     }
 }
 
@@ -111,13 +149,38 @@ const MINIMIZE_WINDOW_ICON: &str    = "🗕";
 const PIN_ICON: &str                = "📌";
 const ADD_ICON: &str                = "➕";
 const WARNING_ICON: &str            = "⚠";
+const CALENDAR_ICON: &str           = "📅";
+const IMPORT_ICON: &str             = "📥";
+const DICE_ICON: &str               = "🎲";
 
 #[derive(Eq, PartialEq)]
 enum Tab {
     Calendar,
+    Teachers,
+    Students,
     Exams,
 }
 
+/// how many days the calendar shows next to each other
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum CalendarView {
+    Day,
+    WorkWeek,
+    Week,
+}
+
+impl CalendarView {
+    const ALL: [CalendarView; 3] = [CalendarView::Day, CalendarView::WorkWeek, CalendarView::Week];
+
+    fn name(&self) -> &'static str {
+        match self {
+            CalendarView::Day => "day",
+            CalendarView::WorkWeek => "work week",
+            CalendarView::Week => "week",
+        }
+    }
+}
+
 #[derive(Eq, PartialEq)]
 enum PersonTab {
     Teachers,
@@ -129,6 +192,7 @@ impl eframe::App for PlanerApp {
         self.update_title(frame);
         self.run_shortcuts(ctx);
         self.data.recompute_if_scheduled();
+        self.poll_solving(ctx);
 
         egui::TopBottomPanel::top("top_status_bar").show(ctx, |ui| {
 
@@ -141,11 +205,11 @@ impl eframe::App for PlanerApp {
                             }
 
                             if ui.add(egui::Button::new("save").shortcut_text("ctrl+s")).clicked() {
-                                self.data.save();
+                                self.save();
                             }
 
                             if ui.add(egui::Button::new("save as").shortcut_text("ctrl+shift+s")).clicked() {
-                                self.data.save_as();
+                                self.save_as();
                             }
 
                             if ui.add(egui::Button::new("open").shortcut_text("ctrl+o")).clicked() {
@@ -156,6 +220,38 @@ impl eframe::App for PlanerApp {
                                 self.edit_template();
                             }
 
+                            ui.separator();
+
+                            if ui.button("export calendars").on_hover_text_at_pointer("write a .ics file for every teacher, student and room").clicked() {
+                                self.export_calendars();
+                                ui.close_menu();
+                            }
+
+                            if ui.button("export html").clicked() {
+                                self.export_html();
+                                ui.close_menu();
+                            }
+
+                            ui.menu_button("export pdf", |ui| {
+                                for layout in PdfLayout::ALL {
+                                    if ui.button(layout.name()).clicked() {
+                                        self.export_pdf(layout);
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+
+                            ui.menu_button("export table", |ui| {
+                                for format in SheetFormat::ALL {
+                                    if ui.button(format.name()).clicked() {
+                                        self.export_sheet(format);
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+
+                            ui.separator();
+
                             if ui.button("settings").clicked() { self.settings.visible = !self.settings.visible }
                         });
 
@@ -165,22 +261,37 @@ impl eframe::App for PlanerApp {
                             }
 
                             if ui.button("import teachers").clicked() {
-                                println!("import teachers");
+                                self.import_table(|data, path| data.import_teachers(path));
+                                ui.close_menu();
+                            }
+
+                            if ui.button("import exams").clicked() {
+                                self.import_table(|data, path| data.import_exams(path));
+                                ui.close_menu();
                             }
 
                             if ui.button("merge plans").clicked() {
                                 println!("merge plans");
                             }
                         });
+
+                        let open = self.data.finished_exams.iter()
+                            .map(|exam| exam.violations.iter().filter(|v| v.is_open()).count())
+                        .sum::<usize>();
+                        if ui.selectable_label(self.conflicts.visible, format!("conflicts ({open})")).clicked() {
+                            self.conflicts.visible = !self.conflicts.visible;
+                        }
                     });
 
-                    col[1].columns(2, |col| {
+                    col[1].columns(4, |col| {
                         let tab = |ui: &mut egui::Ui, is_selected: bool, name: &str| -> egui::Response {
                             ui.add_sized(ui.available_size(), egui::SelectableLabel::new(is_selected, name))
                         };
 
                         if tab(&mut col[0], self.tab == Tab::Calendar, "calendar").clicked() { self.tab = Tab::Calendar }
-                        if tab(&mut col[1], self.tab == Tab::Exams, "exams").clicked() { self.tab = Tab::Exams }
+                        if tab(&mut col[1], self.tab == Tab::Teachers, "teachers").clicked() { self.tab = Tab::Teachers }
+                        if tab(&mut col[2], self.tab == Tab::Students, "students").clicked() { self.tab = Tab::Students }
+                        if tab(&mut col[3], self.tab == Tab::Exams, "exams").clicked() { self.tab = Tab::Exams }
                     });
 
                     // col[2].with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -200,35 +311,53 @@ impl eframe::App for PlanerApp {
             }
         });
 
-        self.settings.ui(ctx);
+        self.settings.ui(ctx, &mut self.data);
+        self.show_reports(ctx);
+
+        if let Some(exam) = self.conflicts.show(ctx, &self.data) {
+            self.show_in_calendar(exam);
+        }
 
         match self.tab {
             Tab::Calendar => self.show_calendar_tab(ctx),
+            Tab::Teachers => {
+                if let Some(err) = self.teacher_schedule.show(ctx, &mut self.data) { self.error_message = Some(err) }
+            },
+            Tab::Students => self.student_schedule.show(ctx, &self.data),
             Tab::Exams => self.show_exams_tab(ctx),
         }
     }
 }
 
+/// the plan before and right after a destructive action, so edits made after it are not undone with it
+struct UndoStep {
+    before: Checkpoint,
+    after: Checkpoint,
+}
+
 #[derive(Debug, Clone)]
-struct DraggingExam(UuidRef<Mutex<Exam>>);
+struct DraggingExam(Id<Exam>);
 
 #[derive(Debug, Clone)]
-struct DraggingTeacher(UuidRef<Mutex<Teacher>>);
+struct DraggingTeacher(Id<Teacher>);
 
 #[derive(Debug, Clone)]
-struct DraggingStudent(UuidRef<Mutex<Student>>);
+struct DraggingStudent(Id<Student>);
 
 impl PlanerApp {
     fn run_shortcuts(&mut self, ctx: &egui::Context) {
+        // text fields have their own undo
+        let editing_text = ctx.memory().focus().is_some();
         let input = ctx.input();
 
         use egui::Modifiers;
-        if input.key_pressed(egui::Key::S) && input.modifiers.command_only() { self.data.save() }
+        if input.key_pressed(egui::Key::S) && input.modifiers.command_only() { self.save() }
         if input.key_pressed(egui::Key::S) &&
             (input.modifiers.matches(Modifiers::SHIFT | Modifiers::CTRL) || input.modifiers.matches(Modifiers::SHIFT | Modifiers::COMMAND))
-        { self.data.save_as() }
+        { self.save_as() }
 
         if input.key_pressed(egui::Key::O) && input.modifiers.command_only() { self.open_file() }
+        if input.key_pressed(egui::Key::Z) && input.modifiers.command_only() && !editing_text { self.undo() }
     }
 
     fn update_title(&self, frame: &mut eframe::Frame) {
@@ -239,6 +368,27 @@ impl PlanerApp {
         frame.set_window_title(&format!("planer - {file_name}"));
     }
 
+    fn save(&mut self) {
+        if let Some(file) = &self.data.current_file_name {
+            if let Err(err) = self.data.save_to(file) {
+                self.error_message = Some(err);
+            }
+        } else {
+            self.save_as();
+        }
+    }
+
+    fn save_as(&mut self) {
+        let file = rfd::FileDialog::new()
+            .add_filter("plans", &["plan"])
+            .add_filter("planer templates", &["ptemplate"])
+            .save_file();
+        if let Some(path) = file {
+            self.data.current_file_name = Some(path.to_str().unwrap().to_owned());
+            self.save();
+        }
+    }
+
     fn open_file(&mut self) {
         let file = rfd::FileDialog::new()
             .add_filter("plans and templates", &["plan", "ptemplate"])
@@ -247,11 +397,13 @@ impl PlanerApp {
             .pick_file();
 
         if let Some(path) = file {
+            self.stop_solving();
             if path.to_str().unwrap().ends_with(".ptemplate") {
                 self.data = PlanerData::load_template(path);
             } else {
                 self.data = PlanerData::load(path);
             }
+            self.undo.clear();
         }
     }
 
@@ -260,10 +412,240 @@ impl PlanerApp {
             .add_filter("planer templates", &["ptemplate"])
             .pick_file();
         if let Some(path) = file {
+            self.stop_solving();
             self.data = PlanerData::load(path);
+            self.undo.clear();
+        }
+    }
+
+    fn clear_unpinned(&mut self, scope: ClearScope) {
+        let label = match scope {
+            ClearScope::All => "clear".to_owned(),
+            ClearScope::Day(day) => format!("clear {}", day.format("%d.%m.%Y")),
+            ClearScope::Room(room) => format!("clear room {}", self.data.rooms.get(room).map_or("", |v| &v.number[..])),
+        };
+
+        let before = match self.data.checkpoint(label) {
+            Ok(v) => v,
+            Err(err) => {
+                self.error_message = Some(err);
+                return
+            },
+        };
+
+        self.data.clear_unpinned(scope);
+        self.data.schedule_recompute();
+        self.push_undo(before);
+    }
+
+    /// the days the calendar shows for the current view
+    fn visible_days(&self) -> Vec<Date<Utc>> {
+        let monday = self.day - Duration::days(self.day.weekday().num_days_from_monday() as i64);
+        let days = match self.view {
+            CalendarView::Day => return vec![self.day],
+            CalendarView::WorkWeek => 5,
+            CalendarView::Week => 7,
+        };
+
+        (0..days).map(|i| monday + Duration::days(i)).collect()
+    }
+
+    /// switches the calendar to the day of a booked exam and scrolls to it
+    fn show_in_calendar(&mut self, exam: Id<Exam>) {
+        let start = match self.data.finished_exams.get(exam).and_then(|v| v.pairing) {
+            Some((_, start)) => start,
+            None => return,
+        };
+
+        self.tab = Tab::Calendar;
+        self.day = start.date();
+        self.focus = Some(exam);
+        self.scroll_to_focus = true;
+    }
+
+    /// starts the solver on a copy of the plan, the result is applied in [`Self::poll_solving`]
+    fn start_solving(&mut self, mode: SolveMode) {
+        if self.solving.is_some() { return }
+
+        match BackgroundSolve::start(&self.data, self.day, mode) {
+            Ok(solving) => self.solving = Some(solving),
+            Err(err) => self.error_message = Some(err),
+        }
+    }
+
+    fn poll_solving(&mut self, ctx: &egui::Context) {
+        let solving = match self.solving.take() {
+            Some(v) if v.is_finished() => v,
+            Some(v) => {
+                // keep the progress up to date while nothing else happens
+                self.solving = Some(v);
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return
+            },
+            None => return,
+        };
+
+        match solving.finish(&mut self.data) {
+            Outcome::Applied { result, previous } => {
+                self.push_undo(previous);
+                if let Err(res) = result {
+                    self.error_message = Some(format!("could not place all exams, placed {} and {} are left", res.finished_exams.len(), self.data.unfinished_exams.len()));
+                }
+            },
+            Outcome::Discarded => self.error_message = Some("the plan was edited while solving, so the result was discarded".to_owned()),
+            Outcome::Cancelled => (),
+            Outcome::Failed(err) => self.error_message = Some(err),
+        }
+    }
+
+    /// cancels a running solver without waiting for it, its result is never applied
+    fn stop_solving(&mut self) {
+        if let Some(solving) = self.solving.take() {
+            solving.cancel();
+        }
+    }
+
+    /// remembers `before` as the plan from before the action that was just done
+    fn push_undo(&mut self, before: Checkpoint) {
+        match self.data.checkpoint(before.label.clone()) {
+            Ok(after) => self.undo.push(UndoStep { before, after }),
+            Err(err) => self.error_message = Some(err),
+        }
+    }
+
+    /// restores the plan from before the last action, unless it was edited since
+    fn undo(&mut self) {
+        let step = match self.undo.pop() {
+            Some(v) => v,
+            None => return,
+        };
+
+        if !self.data.is_at(&step.after) {
+            // the older steps would throw the edits away as well
+            self.undo.clear();
+            self.error_message = Some(format!("the plan was edited after \"{}\", so it cannot be undone anymore", step.before.label));
+        } else if let Err(err) = self.data.restore(&step.before) {
+            self.error_message = Some(err);
+        }
+    }
+
+    fn export_calendars(&mut self) {
+        let dir = rfd::FileDialog::new().pick_folder();
+        if let Some(dir) = dir {
+            if let Err(err) = self.data.export_ics_dir(dir) {
+                self.error_message = Some(format!("could not write calendars: {err}"));
+            }
+        }
+    }
+
+    fn export_html(&mut self) {
+        let dir = rfd::FileDialog::new().pick_folder();
+        if let Some(dir) = dir {
+            if let Err(err) = self.data.export_html(dir, &HtmlOptions::default()) {
+                self.error_message = Some(format!("could not write html pages: {err}"));
+            }
+        }
+    }
+
+    fn export_pdf(&mut self, layout: PdfLayout) {
+        let file = rfd::FileDialog::new()
+            .add_filter("pdf", &["pdf"])
+            .set_file_name(&format!("{}-{}.pdf", self.data.plan_name(), export::file_name(layout.name())))
+            .save_file();
+
+        if let Some(path) = file {
+            if let Err(err) = self.data.export_pdf(layout, path) {
+                self.error_message = Some(err);
+            }
+        }
+    }
+
+    fn export_sheet(&mut self, format: SheetFormat) {
+        let file = rfd::FileDialog::new()
+            .add_filter(format.extension(), &[format.extension()])
+            .set_file_name(&format!("{}-bookings.{}", self.data.plan_name(), format.extension()))
+            .save_file();
+
+        if let Some(path) = file {
+            if let Err(err) = self.data.export_sheet(format, path) {
+                self.error_message = Some(err);
+            }
+        }
+    }
+
+    fn save_ics(name: &str, content: String) -> Result<(), String> {
+        let file = rfd::FileDialog::new()
+            .add_filter("calendars", &["ics"])
+            .set_file_name(&format!("{}.ics", export::file_name(name)))
+            .save_file();
+
+        match file {
+            Some(path) => std::fs::write(path, content).map_err(|err| format!("could not write file: {err}")),
+            None => Ok(()),
         }
     }
 
+    fn import_blocked_times(calendar: &mut Calendar<BlockedTime>, timezone: Tz) -> Option<Result<ImportReport, String>> {
+        let file = rfd::FileDialog::new()
+            .add_filter("calendars", &["ics"])
+            .pick_file();
+
+        file.map(|path| import::ics::import_blocked_times(calendar, path, timezone))
+    }
+
+    fn import_table(&mut self, import: impl FnOnce(&mut PlanerData, std::path::PathBuf) -> Result<ImportReport, String>) {
+        let file = rfd::FileDialog::new()
+            .add_filter("tables", &["csv", "txt"])
+            .pick_file();
+
+        if let Some(path) = file {
+            self.import_report = Some(import(&mut self.data, path));
+        }
+    }
+
+    fn show_reports(&mut self, ctx: &egui::Context) {
+        let mut open = self.import_report.is_some();
+        if let Some(report) = &self.import_report {
+            egui::Window::new("import")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+            .show(ctx, |ui| {
+                match report {
+                    Ok(report) => {
+                        ui.label(report.summary());
+                        if !report.warnings.is_empty() {
+                            ui.separator();
+                            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                                for warning in &report.warnings {
+                                    ui.label(egui::RichText::new(format!("{WARNING_ICON} {warning}")).color(egui::Color32::YELLOW));
+                                }
+                            });
+                        }
+                    },
+                    Err(err) => { ui.label(egui::RichText::new(err).color(egui::Color32::RED)); },
+                }
+            });
+        }
+
+        if !open { self.import_report = None }
+
+        let mut open = self.error_message.is_some();
+        if let Some(err) = &self.error_message {
+            egui::Window::new("error")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(err).color(egui::Color32::RED));
+            });
+        }
+
+        if !open { self.error_message = None }
+    }
+
     fn show_calendar_tab(&mut self, ctx: &egui::Context) {
         egui::SidePanel::right("exam_select_panel").resizable(true).min_width(200.0).show(ctx, |ui| {
 
@@ -274,14 +656,12 @@ impl PlanerApp {
             egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
 
                 let mut finish_exam = None;
-                for (i, exam) in self.data.unfinished_exams.iter().enumerate() {
-                    let uuid = { UuidRef::new(exam) };
+                for (i, exam) in self.data.unfinished_exams.iter_mut().enumerate() {
+                    let id = exam.id();
                     drag_source(ui, ui.id().with((i, "exam_drag_calendar")), |ui| {
-                        let mut exam = exam.lock().unwrap();
-
-                        Self::show_exam(ui, &mut exam, ExamView::InSearch, || {})
-                    }, || DraggingExam(uuid.clone()), || {
-                        finish_exam = Some(uuid.clone());
+                        Self::show_exam(ui, exam, &self.data.teachers, &self.data.students, ExamView::InSearch, || {})
+                    }, || DraggingExam(id), || {
+                        finish_exam = Some(id);
                     });
                 }
                 finish_exam.map(|v| self.data.finish_exam(v));
@@ -299,18 +679,103 @@ impl PlanerApp {
         egui::TopBottomPanel::top("compute_panel").show(ctx, |ui| {
             egui::Frame::none().inner_margin(2.0).show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    if ui.button("compute")
-                        .on_hover_text_at_pointer("try to assign rooms and times to all unfinished exams")
-                    .clicked() {
-                        self.data.solve();
-                        println!("compute");
+                    match &self.solving {
+                        Some(solving) => {
+                            let progress = solving.progress();
+
+                            ui.spinner();
+                            ui.label(format!("placed {} exams, {} remaining", progress.placed, progress.remaining));
+                            if let Some(score) = progress.best_score {
+                                ui.label(format!("best score {score}"));
+                            }
+                            ui.label(format!("{:.1}s", solving.elapsed().as_secs_f32()));
+
+                            if ui.button("cancel").clicked() {
+                                solving.cancel();
+                            }
+                        },
+                        None => {
+                            if ui.button("compute")
+                                .on_hover_text_at_pointer("try to assign rooms and times to all unfinished exams")
+                            .clicked() {
+                                self.start_solving(SolveMode::Full);
+                            }
+
+                            if ui.button("re-solve")
+                                .on_hover_text_at_pointer("place the unfinished exams and move as few unpinned exams as possible to fix conflicts")
+                            .clicked() {
+                                self.start_solving(SolveMode::Incremental);
+                            }
+
+                            ui.add(egui::DragValue::new(&mut self.data.solver.seed).prefix("seed "))
+                                .on_hover_text_at_pointer("decides between equally good placements, it is saved with the plan so the result can be reproduced");
+                            if ui.button(DICE_ICON).on_hover_text_at_pointer("pick a random seed").clicked() {
+                                self.data.solver.seed = Uuid::new_v4().as_u64_pair().0;
+                            }
+                        },
                     }
 
                     if ui.button("clear")
                         .on_hover_text_at_pointer("clear all unpinned exams")
                     .clicked() {
-                        println!("clear");
+                        self.clear_unpinned(ClearScope::All);
                     }
+
+                    ui.menu_button("clear only", |ui| {
+                        for day in self.visible_days() {
+                            if ui.button(format!("day {}", day.format("%d.%m.%Y"))).clicked() {
+                                self.clear_unpinned(ClearScope::Day(day));
+                                ui.close_menu();
+                            }
+                        }
+
+                        ui.separator();
+
+                        let rooms: Vec<_> = self.data.rooms.iter().map(|v| (v.id(), v.number.clone())).collect();
+                        for (room, number) in rooms {
+                            if ui.button(format!("room {number}")).clicked() {
+                                self.clear_unpinned(ClearScope::Room(room));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+
+                    if let Some(step) = self.undo.last() {
+                        if ui.button(format!("undo {}", step.before.label))
+                            .on_hover_text_at_pointer("ctrl+z, only if the plan was not edited since")
+                        .clicked() {
+                            self.undo();
+                        }
+                    }
+
+                    ui.separator();
+
+                    let step = match self.view {
+                        CalendarView::Day => Duration::days(1),
+                        CalendarView::WorkWeek | CalendarView::Week => Duration::weeks(1),
+                    };
+                    if ui.button("◀").on_hover_text_at_pointer("previous").clicked() { self.day = self.day - step }
+                    date_picker(ui, ui.id().with("calendar_day"), &mut self.day);
+                    if ui.button("▶").on_hover_text_at_pointer("next").clicked() { self.day = self.day + step }
+                    if ui.add_enabled(self.day != Utc::today(), egui::Button::new("today")).clicked() { self.day = Utc::today() }
+
+                    egui::ComboBox::from_id_source("calendar_view")
+                        .selected_text(self.view.name())
+                    .show_ui(ui, |ui| {
+                        for view in CalendarView::ALL {
+                            ui.selectable_value(&mut self.view, view, view.name());
+                        }
+                    });
+
+                    ui.menu_button("rooms", |ui| {
+                        for room in self.data.rooms.iter() {
+                            let id = room.id();
+                            let mut shown = !self.hidden_rooms.contains(&id);
+                            if ui.checkbox(&mut shown, &room.number[..]).changed() {
+                                if shown { self.hidden_rooms.retain(|v| *v != id) } else { self.hidden_rooms.push(id) }
+                            }
+                        }
+                    });
                 });
             });
         });
@@ -325,25 +790,64 @@ impl PlanerApp {
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            let current_day = Utc::today();
+            let days = self.visible_days();
+            let multi_day = days.len() > 1;
             // <marker>
             let time_width = 50.0;
-            let room_width = 200.0;
-            let header_height = 100.0;
+            let room_width = if multi_day { 150.0 } else { 200.0 };
+            let day_height = if multi_day { 25.0 } else { 0.0 };
+            let header_height = if multi_day { 40.0 } else { 100.0 } + day_height;
             let padding = 5.0;
+
+            // the rooms of a day are next to each other, the days are separated by a gap
+            let rooms_shown = self.data.rooms.iter().filter(|room| !self.hidden_rooms.contains(&room.id())).count();
+            let column_x = |day: usize, room: usize| {
+                (room_width + padding * 2.0) * ((day * rooms_shown + room) as f32) + time_width + padding * 2.0 + day as f32 * padding * 2.0
+            };
+
             egui::ScrollArea::new([true; 2]).auto_shrink([false; 2]).show(ui, |ui| {
                 let top_left = ui.min_rect().left_top();
                 // manualy set dims
-                ui.set_width((room_width + padding * 2.0) * (self.data.rooms.len() as f32) + time_width + padding * 2.0);
+                ui.set_width(column_x(days.len(), 0));
+
+                if multi_day {
+                    let day_width = (rooms_shown as f32 * (room_width + padding * 2.0) - padding * 2.0).max(room_width);
+                    for (d, day) in days.iter().enumerate() {
+                        let summary = self.data.day_summary(*day);
+                        let rect = egui::Rect::from_min_size(top_left + vec2(column_x(d, 0), 0.0), vec2(day_width, day_height));
+
+                        let mut ui = ui.child_ui(rect, egui::Layout::top_down(egui::Align::Center));
+                        let label = egui::SelectableLabel::new(*day == self.day, egui::RichText::new(day.format("%A %d.%m.").to_string()).strong());
+                        if ui.add_sized(rect.size(), label)
+                            .on_hover_text_at_pointer(format!(
+                                "{} exams, {} pinned\n{} rooms, {} teachers, {} students\n{} conflicts, {} warnings\n\nclick to show only this day",
+                                summary.exams, summary.pinned, summary.rooms, summary.teachers, summary.students, summary.conflicts, summary.warnings,
+                            ))
+                        .clicked() {
+                            self.day = *day;
+                            self.view = CalendarView::Day;
+                        }
+                    }
+                }
 
-                let mut delete_idx = None;
-                for (i, room) in self.data.rooms.iter_mut().enumerate() {
-                    let mut room = room.lock().unwrap();
-                    let idx = i as f32;
-                    let rect = egui::Rect::from_min_size(
-                                                // (room_width + padding * 2.0) * (i as f32) + time_width + padding * 2.0,
-                        top_left + vec2(idx * (room_width + padding * 2.0) + time_width + padding * 2.0, 0.0),
-                        vec2(room_width, header_height));
+                let mut delete_room = None;
+                let mut export_room = None;
+                let mut import_report = None;
+                let timezone = self.data.timezone;
+                for (i, room) in self.data.rooms.iter_mut().filter(|room| !self.hidden_rooms.contains(&room.id())).enumerate() {
+                    // rooms are only edited in the day view
+                    if multi_day {
+                        for d in 0..days.len() {
+                            let rect = egui::Rect::from_min_size(top_left + vec2(column_x(d, i), day_height), vec2(room_width, header_height - day_height));
+                            ui.child_ui(rect, egui::Layout::top_down(egui::Align::Center)).group(|ui| {
+                                ui.vertical_centered_justified(|ui| ui.heading(&room.number[..]));
+                                ui.allocate_space(ui.available_size());
+                            });
+                        }
+                        continue;
+                    }
+
+                    let rect = egui::Rect::from_min_size(top_left + vec2(column_x(0, i), 0.0), vec2(room_width, header_height));
 
                     let mut ui = ui.child_ui(rect, egui::Layout::left_to_right(egui::Align::TOP));
 
@@ -388,14 +892,36 @@ impl PlanerApp {
                                 if ui.button(CLOSE_WINDOW_ICON)
                                     .on_hover_text_at_pointer("click to delete")
                                 .clicked() {
-                                    delete_idx = Some(i);
+                                    delete_room = Some(room.id());
+                                }
+
+                                Self::buffer_edit(ui, &mut room.buffer);
+
+                                if ui.button(CALENDAR_ICON)
+                                    .on_hover_text_at_pointer("export calendar (.ics)")
+                                .clicked() {
+                                    export_room = Some(room.id());
+                                }
+
+                                if ui.button(IMPORT_ICON)
+                                    .on_hover_text_at_pointer("import blocked times (.ics)")
+                                .clicked() {
+                                    if let Some(report) = Self::import_blocked_times(&mut room.blocked, timezone) { import_report = Some(report) }
                                 }
                             });
                         });
                     });
                 }
 
-                if let Some(idx) = delete_idx { self.data.rooms.remove(idx); }
+                if let Some(id) = export_room {
+                    let room = &self.data.rooms[id];
+                    if let Err(err) = Self::save_ics(&format!("room-{}", room.number), ics::room_ics(&self.data, room)) { self.error_message = Some(err) }
+                }
+                if let Some(id) = delete_room { self.data.rooms.remove(id); }
+                if import_report.is_some() {
+                    self.import_report = import_report;
+                    self.data.schedule_recompute();
+                }
 
                 let minute_height = 2.0;
                 // let rect = egui::Rect::from_min_size(
@@ -415,6 +941,16 @@ impl PlanerApp {
                         ui.set_height(total_height);
 
                         let mut remove_exam = None;
+                        let mut unbook_exams = Vec::new();
+                        let mut book_exams = Vec::new();
+
+                        // the free slots of the dragged exam are highlighted
+                        let dragged = dragged::<DraggingExam>(ui).and_then(|v| Some((self.data.exam(v.0)?.duration, v.0)));
+                        let slots = match dragged {
+                            Some((_, exam)) => self.data.find_slots(exam, TimeRange::new(days[0].and_hms(0, 0, 0), days[days.len() - 1].succ().and_hms(0, 0, 0))),
+                            None => Vec::new(),
+                        };
+
                         for (j, lesson) in self.data.timetable.times.iter().enumerate() {
                             let start = lesson.start.signed_duration_since(start_t).num_minutes() as f32;
                             let duration = lesson.duration.num_minutes() as f32;
@@ -434,85 +970,102 @@ impl PlanerApp {
                                 });
                             });
 
-                            let mut needs_recompute = false;
-                            for (i, room) in self.data.rooms.iter().enumerate() {
-                                let room_ref = room.clone();
-                                let mut room = room.lock().unwrap();
-
-                                let lesson_start = current_day.and_time(lesson.start).unwrap();
-                                let bookings = room.calendar.get_events_at(&lesson_start);
-                                let mut should_unbook = false;
-                                let mut should_unbook_2 = false;
-                                let booking = bookings.last();
-                                if let Some(booking) = booking {
-                                    let exam = booking.data.clone();
-                                    if booking.start == lesson_start {
-                                        let duration = booking.duration.num_minutes() as f32;
+                            for (d, day) in days.iter().enumerate() {
+                                for (i, room) in self.data.rooms.iter().filter(|room| !self.hidden_rooms.contains(&room.id())).enumerate() {
+                                    let room_id = room.id();
+                                    let lesson_start = day.and_time(lesson.start).unwrap();
+                                    let bookings = room.calendar.get_events_at(&lesson_start);
+                                    let mut should_unbook = false;
+                                    let mut should_unbook_2 = false;
+                                    let booking = bookings.last();
+                                    if let Some(booking) = booking {
+                                        let exam_id = booking.data;
+                                        if booking.start == lesson_start {
+                                            let duration = booking.duration.num_minutes() as f32;
+                                            let rect = egui::Rect::from_min_size(
+                                                top_left + vec2(
+                                                    column_x(d, i),
+                                                    start * minute_height + header_height + padding * 2.0),
+                                                vec2(room_width, duration * minute_height),
+                                            );
+                                            let mut ui = ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP));
+
+                                            ui.push_id(("room_exam_trag_container", d, i, j), |ui| {
+                                                let id = ui.id().with(("room_exam_drag", i, &room.number[..]));
+                                                let (teachers, students) = (&self.data.teachers, &self.data.students);
+                                                if let Some(exam) = self.data.finished_exams.get_mut(exam_id) {
+                                                    if exam.pinned {
+                                                        Self::show_exam(ui, exam, teachers, students, ExamView::InRoom, || {
+                                                            should_unbook_2 = true;
+                                                            remove_exam = Some(exam_id);
+                                                        });
+                                                    } else {
+                                                        drag_source(ui, id, |ui| {
+                                                            Self::show_exam(ui, exam, teachers, students, ExamView::InRoom, || {
+                                                                should_unbook_2 = true;
+                                                                remove_exam = Some(exam_id);
+                                                            })
+                                                        }, || {
+                                                            DraggingExam(exam_id)
+                                                        }, || {
+                                                            should_unbook = true;
+                                                        });
+                                                    }
+                                                } else { ui.label("<invalid>"); }
+                                            });
+
+                                            if self.focus == Some(exam_id) {
+                                                ui.painter().rect_stroke(rect.expand(3.0), 4.0, egui::Stroke::new(3.0, ui.visuals().selection.stroke.color));
+                                                if self.scroll_to_focus {
+                                                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                                                    self.scroll_to_focus = false;
+                                                }
+                                            }
+
+                                        }
+
+                                        if should_unbook || should_unbook_2 {
+                                            unbook_exams.push(exam_id);
+                                        }
+                                    } else {
                                         let rect = egui::Rect::from_min_size(
                                             top_left + vec2(
-                                                (room_width + padding * 2.0) * (i as f32) + time_width + padding * 2.0,
+                                                column_x(d, i),
                                                 start * minute_height + header_height + padding * 2.0),
                                             vec2(room_width, duration * minute_height),
                                         );
+
                                         let mut ui = ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP));
 
-                                        ui.push_id(("room_exam_trag_container", i, j), |ui| {
-                                            let id = ui.id().with(("room_exam_drag", i, &room.number[..]));
-                                            if let Some(exam) = booking.data.get() {
-                                                let mut exam = exam.lock().unwrap();
-                                                if exam.pinned {
-                                                    Self::show_exam(ui, &mut exam, ExamView::InRoom, || {
-                                                        should_unbook_2 = true;
-                                                        remove_exam = Some(booking.data.clone());
-                                                    });
-                                                } else {
-                                                    drag_source(ui, id, |ui| {
-                                                        Self::show_exam(ui, &mut exam, ExamView::InRoom, || {
-                                                            should_unbook_2 = true;
-                                                            remove_exam = Some(booking.data.clone());
-                                                        })
-                                                    }, || {
-                                                        // DraggingExam(booking.data)
-                                                        DraggingExam(booking.data.clone())
-                                                    }, || {
-                                                        should_unbook = true;
-                                                    });
-                                                }
-                                            } else { ui.label("<invalid>"); }
+                                        let blocked: Vec<_> = room.blocked.get_booked_from_to(&lesson_start, lesson.duration).iter()
+                                            .map(|v| v.data.summary.clone())
+                                        .collect();
+
+                                        let fits = dragged.map_or(false, |(duration, _)| {
+                                            slots.iter().any(|v| v.room == room_id && v.fits(lesson_start, duration))
                                         });
 
+                                        egui::Frame::none().inner_margin(2.0).show(&mut ui, |ui| {
+                                            highlighted_drop_target(ui, fits, |ui| {
+                                                if !blocked.is_empty() {
+                                                    ui.weak("blocked").on_hover_text_at_pointer(blocked.join("\n"));
+                                                }
+                                                ui.allocate_space(ui.available_size());
+                                            }, |v: DraggingExam| {
+                                                book_exams.push((v.0, room_id, lesson_start));
+                                            });
+                                        });
                                     }
+                                    ui.allocate_space(ui.available_size());
 
-                                    if should_unbook || should_unbook_2 {
-                                        needs_recompute = true;
-                                        PlanerData::unbook_exam(exam, &mut *room, lesson_start);
-                                    }
-                                } else {
-                                    let rect = egui::Rect::from_min_size(
-                                        top_left + vec2(
-                                            (room_width + padding * 2.0) * (i as f32) + time_width + padding * 2.0,
-                                            start * minute_height + header_height + padding * 2.0),
-                                        vec2(room_width, duration * minute_height),
-                                    );
-
-                                    let mut ui = ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP));
-
-                                    drop(room);
-
-                                    egui::Frame::none().inner_margin(2.0).show(&mut ui, |ui| {
-                                        drop_target(ui, |ui| {
-                                            ui.allocate_space(ui.available_size());
-                                        }, |v: DraggingExam| {
-                                            PlanerData::book_exam(v.0, &room_ref, lesson_start);
-                                            needs_recompute = true;
-                                        });
-                                    });
                                 }
-                                ui.allocate_space(ui.available_size());
-
                             }
                             self.data.schedule_recompute();
                         }
+
+                        // exams moved inside the grid are unbooked before they are booked again
+                        for exam in unbook_exams { self.data.unbook_exam(exam) }
+                        for (exam, room, start) in book_exams { self.data.book_exam(exam, room, start) }
                         remove_exam.map(|v| self.data.unfinish_exam(v));
                     }
                         
@@ -583,7 +1136,7 @@ impl PlanerApp {
 
                                     let mut shorthand = data.shorthand.take().unwrap_or_default();
                                     egui::TextEdit::singleline(&mut shorthand)
-                                        .hint_text(format!("{} (shorthand)", &data.last_name[0..(data.last_name.len().min(2))]))
+                                        .hint_text(format!("{} (shorthand)", default_shorthand(&data.last_name)))
                                     .show(ui);
                                     if !shorthand.is_empty() { data.shorthand = Some(shorthand) }
 
@@ -607,11 +1160,13 @@ impl PlanerApp {
                     });
 
                     egui::ScrollArea::vertical().auto_shrink([false; 2]).stick_to_bottom(true).show(ui, |ui| {
-                        let mut delete_idx = None;
-                        for (i, teacher) in self.data.teachers.iter()
-                        .filter(|v| {
+                        let mut delete_teacher = None;
+                        let mut export_teacher = None;
+                        let mut import_report = None;
+                        let timezone = self.data.timezone;
+                        for (i, t) in self.data.teachers.iter_mut()
+                        .filter(|teacher| {
                             let (s_str, s_type) = self.search_data.search();
-                            let teacher = v.lock().unwrap();
                             match s_type {
                                 SearchType::Normal | SearchType::Name => { format!("{}", teacher.name).to_uppercase().contains(&s_str.to_uppercase()) },
                                 SearchType::Subject => { teacher.subjects.iter().find(|v| v.to_uppercase().contains(&s_str.to_uppercase())).is_some() },
@@ -619,8 +1174,7 @@ impl PlanerApp {
                             }
                         }).enumerate() {
                             {
-                                let dragging_teacher = DraggingTeacher(UuidRef::new(teacher));
-                                let mut t = teacher.lock().unwrap();
+                                let dragging_teacher = DraggingTeacher(t.id());
                                 let name = t.name.clone();
                                 let mut set_name = None;
                                 egui::Frame::default().fill(ui.style().noninteractive().bg_fill).show(ui, |ui| {
@@ -663,6 +1217,7 @@ impl PlanerApp {
                                         // if ui.add_sized((ui.available_width(), 0.0), egui::Button::new(egui::RichText::new(format!("{}", t.name)).heading()))
 
                                         ui.add_sized((ui.available_width(), 0.0), egui::TextEdit::singleline(&mut t.shorthand).hint_text("shorthand"));
+                                        Self::buffer_edit(ui, &mut t.buffer);
 
                                         ui.separator();
                                         ui.allocate_space(vec2(ui.available_width(), 0.0));
@@ -711,10 +1266,22 @@ impl PlanerApp {
                                         });
 
                                         ui.columns(3, |col| {
+                                            if col[0].add_sized(col[0].min_size(), egui::Button::new(CALENDAR_ICON))
+                                                .on_hover_text_at_pointer("export calendar (.ics)")
+                                            .clicked() {
+                                                export_teacher = Some(t.id());
+                                            }
+
+                                            if col[1].add_sized(col[1].min_size(), egui::Button::new(IMPORT_ICON))
+                                                .on_hover_text_at_pointer(format!("import absences (.ics), {} blocked times", t.blocked.events().len()))
+                                            .clicked() {
+                                                if let Some(report) = Self::import_blocked_times(&mut t.blocked, timezone) { import_report = Some(report) }
+                                            }
+
                                             if col[2].add_sized(col[2].min_size(), egui::Button::new(CLOSE_WINDOW_ICON))
                                                 .on_hover_text_at_pointer("click to remove")
                                             .clicked() {
-                                                delete_idx = Some(i);
+                                                delete_teacher = Some(t.id());
                                             }
                                         });
                                     });
@@ -724,8 +1291,18 @@ impl PlanerApp {
                             }
                         }
 
-                        if let Some(idx) = delete_idx {
-                            self.data.teachers.remove(idx);
+                        if let Some(id) = export_teacher {
+                            let teacher = &self.data.teachers[id];
+                            if let Err(err) = Self::save_ics(&format!("teacher-{}", teacher.shorthand), ics::teacher_ics(&self.data, teacher)) { self.error_message = Some(err) }
+                        }
+
+                        if let Some(id) = delete_teacher {
+                            self.data.teachers.remove(id);
+                        }
+
+                        if import_report.is_some() {
+                            self.import_report = import_report;
+                            self.data.schedule_recompute();
                         }
                     });
 
@@ -778,19 +1355,18 @@ impl PlanerApp {
                     });
 
                     egui::ScrollArea::vertical().auto_shrink([false; 2]).stick_to_bottom(true).show(ui, |ui| {
-                        let mut delete_idx = None;
-                        for (i, student) in self.data.students.iter()
-                        .filter(|v| {
+                        let mut delete_student = None;
+                        let mut export_student = None;
+                        for (i, t) in self.data.students.iter_mut()
+                        .filter(|student| {
                             let (s_str, s_type) = self.search_data.search();
-                            let student = v.lock().unwrap();
                             match s_type {
                                 SearchType::Normal | SearchType::Name => { format!("{}", student.name).to_uppercase().contains(&s_str.to_uppercase()) },
                                 _ => false,
                             }
                         }).enumerate() {
                             {
-                                let dragging_student = DraggingStudent(UuidRef::new(student));
-                                let mut t = student.lock().unwrap();
+                                let dragging_student = DraggingStudent(t.id());
                                 let name = t.name.clone();
                                 let mut set_name = None;
                                 egui::Frame::default().fill(ui.style().noninteractive().bg_fill).show(ui, |ui| {
@@ -832,15 +1408,22 @@ impl PlanerApp {
                                         ui.allocate_space(vec2(ui.available_width(), 0.0));
                                         // if ui.add_sized((ui.available_width(), 0.0), egui::Button::new(egui::RichText::new(format!("{}", t.name)).heading()))
 
+                                        Self::buffer_edit(ui, &mut t.buffer);
 
                                         ui.separator();
                                         ui.allocate_space(vec2(ui.available_width(), 0.0));
 
                                         ui.columns(3, |col| {
+                                            if col[0].add_sized(col[0].min_size(), egui::Button::new(CALENDAR_ICON))
+                                                .on_hover_text_at_pointer("export calendar (.ics)")
+                                            .clicked() {
+                                                export_student = Some(t.id());
+                                            }
+
                                             if col[2].add_sized(col[2].min_size(), egui::Button::new(CLOSE_WINDOW_ICON))
                                                 .on_hover_text_at_pointer("click to remove")
                                             .clicked() {
-                                                delete_idx = Some(i);
+                                                delete_student = Some(t.id());
                                             }
                                         });
                                     });
@@ -850,8 +1433,13 @@ impl PlanerApp {
                             }
                         }
 
-                        if let Some(idx) = delete_idx {
-                            self.data.students.remove(idx);
+                        if let Some(id) = export_student {
+                            let student = &self.data.students[id];
+                            if let Err(err) = Self::save_ics(&format!("student-{}", student.name), ics::student_ics(&self.data, student)) { self.error_message = Some(err) }
+                        }
+
+                        if let Some(id) = delete_student {
+                            self.data.students.remove(id);
                         }
                     });
                 },
@@ -891,11 +1479,12 @@ impl PlanerApp {
                     ui.set_min_size(vec2(min_width, 0.0));
                     let num_cols = ((ui.available_size().x / min_width) as usize).max(1);
 
-                    for (i, exams) in self.data.unfinished_exams.chunks(num_cols).enumerate() {
+                    let mut exams: Vec<_> = self.data.unfinished_exams.iter_mut().collect();
+                    for exams in exams.chunks_mut(num_cols) {
                         ui.columns(num_cols, |col| {
-                            for (j, (exam, ui)) in exams.iter().zip(col.iter_mut()).enumerate() {
-                                let mut exam = exam.lock().unwrap();
-                                Self::show_exam(ui, &mut exam, ExamView::Edit, || remove_exam = Some(i * num_cols + j));
+                            for (exam, ui) in exams.iter_mut().zip(col.iter_mut()) {
+                                let id = exam.id();
+                                Self::show_exam(ui, exam, &self.data.teachers, &self.data.students, ExamView::Edit, || remove_exam = Some(id));
                             }
                         });
                     }
@@ -904,13 +1493,8 @@ impl PlanerApp {
                     // editing
                 });
 
-                if let Some(idx) = remove_exam {
-                    let mut i = 0;
-                    self.data.unfinished_exams.retain(|_| {
-                        let remove = i != idx;
-                        i += 1;
-                        remove
-                    });
+                if let Some(id) = remove_exam {
+                    self.data.unfinished_exams.remove(id);
                 }
 
             } else {
@@ -922,9 +1506,23 @@ impl PlanerApp {
         });
     }
 
-    fn show_exam(ui: &mut egui::Ui, exam: &mut Exam, view: ExamView, on_remove: impl FnOnce()) -> Option<egui::Response> {
-        let frame_color = if matches!(view, ExamView::InRoom) && exam.error.is_some() { egui::Stroke::new(2.0, egui::Color32::DARK_RED) }
-                          else { ui.style().noninteractive().bg_stroke };
+    /// edits the changeover time that has to stay free between two exams
+    fn buffer_edit(ui: &mut egui::Ui, buffer: &mut Duration) {
+        let mut minutes = buffer.num_minutes();
+        ui.horizontal(|ui| {
+            ui.label("buffer: ");
+            ui.add(egui::DragValue::new(&mut minutes).clamp_range(0..=120).suffix("min"))
+                .on_hover_text_at_pointer("time that has to stay free between two exams");
+        });
+        *buffer = Duration::minutes(minutes);
+    }
+
+    fn show_exam(ui: &mut egui::Ui, exam: &mut Exam, teachers: &Store<Teacher>, students: &Store<Student>, view: ExamView, on_remove: impl FnOnce()) -> Option<egui::Response> {
+        let frame_color = match exam.worst_open() {
+            Some(Severity::Error) if matches!(view, ExamView::InRoom) => egui::Stroke::new(2.0, egui::Color32::DARK_RED),
+            Some(Severity::Warning) if matches!(view, ExamView::InRoom) => egui::Stroke::new(2.0, egui::Color32::from_rgb(180, 140, 0)),
+            _ => ui.style().noninteractive().bg_stroke,
+        };
 
         let res = egui::Frame::group(ui.style())
             .fill(ui.style().noninteractive().bg_fill)
@@ -946,6 +1544,14 @@ impl PlanerApp {
                             ui.add(egui::DragValue::new(&mut minutes).speed(2.0).suffix("min"));
                         });
                         exam.duration = Duration::minutes(minutes);
+
+                        let mut minutes = exam.preparation.num_minutes();
+                        ui.horizontal(|ui| {
+                            ui.label("preparation: ");
+                            ui.add(egui::DragValue::new(&mut minutes).clamp_range(0..=120).suffix("min"))
+                                .on_hover_text_at_pointer("time the examinees get to prepare right before the exam");
+                        });
+                        exam.preparation = Duration::minutes(minutes);
                     }
 
                     ui.separator();
@@ -996,9 +1602,8 @@ impl PlanerApp {
                         ui.weak("examiners");
                         ui.columns(exam.examiners.len(), |col| {
                             for (examiner, ui) in exam.examiners.iter_mut().zip(col.iter_mut()) {
-                                if let Some(v) = examiner {
-                                    if let Some(v) = v.get() {
-                                        let v = v.lock().unwrap();
+                                if let Some(id) = examiner {
+                                    if let Some(v) = teachers.get(*id) {
                                         ui.add_space(2.0);
                                         let res = ui.button(format!("{}", v.shorthand))
                                             .on_hover_text_at_pointer(format!("{}", v.name))
@@ -1012,7 +1617,7 @@ impl PlanerApp {
                                     } else {
                                         ui.add_space(2.0);
                                         let res = ui.button(egui::RichText::new("<invalid>").color(egui::Color32::RED))
-                                            .on_hover_text_at_pointer(format!("uuid \"{}\" is invalid", v.uuid()))
+                                            .on_hover_text_at_pointer(format!("uuid \"{}\" is invalid", id.uuid()))
                                             .on_hover_text_at_pointer(format!("click to revalidate, right-click to remove"));
                                         // todo add click to revalidate func
                                         if res.secondary_clicked() { *examiner = None }
@@ -1036,9 +1641,8 @@ impl PlanerApp {
                         ui.weak("examinees");
                         drop_target(ui, |ui| {
                             ui.horizontal_wrapped(|ui| {
-                                exam.examinees.retain(|v| {
-                                    if let Some(v) = v.get() {
-                                        let v = v.lock().unwrap();
+                                exam.examinees.retain(|id| {
+                                    if let Some(v) = students.get(*id) {
                                         let res = ui.button(format!("{}", v.name)).on_hover_text_at_pointer("click to jump to, right-click to remove");
 
                                         // todo: implement click to jump to
@@ -1046,7 +1650,7 @@ impl PlanerApp {
                                         !res.secondary_clicked()
                                     } else {
                                         let res = ui.button(egui::RichText::new("<invalid>").color(egui::Color32::RED))
-                                            .on_hover_text_at_pointer(format!("uuid: \"{}\" is invalid", v.uuid()))
+                                            .on_hover_text_at_pointer(format!("uuid: \"{}\" is invalid", id.uuid()))
                                             .on_hover_text_at_pointer("click to revalidate, right-click to remove");
 
                                         // todo add click to revalidate fn
@@ -1125,9 +1729,23 @@ impl PlanerApp {
                                     exam.pinned = !exam.pinned;
                                 }
 
-                                if let Some(err) = &exam.error {
-                                    ui.button(egui::RichText::new(WARNING_ICON).color(egui::Color32::YELLOW))
-                                        .on_hover_text_at_pointer(err);
+                                let mut toggle = None;
+                                for (i, violation) in exam.violations.iter().enumerate() {
+                                    let (icon, color) = severity_icon(violation.severity);
+                                    let mut text = egui::RichText::new(icon).color(color);
+                                    if violation.acknowledged { text = text.weak() }
+
+                                    let hint = if !violation.can_acknowledge() { "" }
+                                               else if violation.acknowledged { "\n\nclick to take the acknowledgement back" }
+                                               else { "\n\nclick to acknowledge" };
+                                    if ui.button(text)
+                                        .on_hover_text_at_pointer(format!("{}: {}{hint}", violation.rule, violation.message))
+                                    .clicked() {
+                                        toggle = Some(i);
+                                    }
+                                }
+                                if let Some(i) = toggle {
+                                    exam.toggle_acknowledged(i);
                                 }
                             });
                         }
@@ -1148,9 +1766,8 @@ impl PlanerApp {
                             ui.columns(exam.examiners.len(), |col| {
                                 for (examiner, ui) in exam.examiners.iter().zip(col.iter_mut()) {
                                     ui.centered_and_justified(|ui| {
-                                        if let Some(v) = examiner {
-                                            if let Some(v) = v.get() {
-                                                let v = v.lock().unwrap();
+                                        if let Some(id) = examiner {
+                                            if let Some(v) = teachers.get(*id) {
                                                 ui.label(format!("{}", v.shorthand)).on_hover_text_at_pointer(format!("{}", v.name));
                                             } else {
                                                 ui.label(egui::RichText::new("<invalid>").color(egui::Color32::RED));
@@ -1166,8 +1783,7 @@ impl PlanerApp {
                             ui.horizontal_wrapped(|ui| {
                                 for (i, examinee) in exam.examinees.iter().enumerate() {
                                     let comma = if (i + 1) < exam.examinees.len() { ", " } else { "" };
-                                    if let Some(examinee) = examinee.get() {
-                                        let examinee = examinee.lock().unwrap();
+                                    if let Some(examinee) = students.get(*examinee) {
                                         ui.label(format!("{}{}", examinee.name, comma));
                                     } else {
                                         ui.label(egui::RichText::new(format!("<invalid>{}", comma)).color(egui::Color32::RED));
@@ -1201,19 +1817,38 @@ impl PlanerApp {
     }
 }
 
-
 struct Settings {
     visible: bool,
+    script_editor: Option<ScriptEditor>,
 }
 
+/// the editor of a scripted rule
+struct ScriptEditor {
+    /// the index of the rule in the rule set
+    rule: usize,
+    /// the result of the last test run
+    test: Option<Result<String, String>>,
+}
+
+const SCRIPT_TEMPLATE: &str = "\
+// return true if the exam may take place in the room at this time,
+// false or a reason if not (a number for soft constraints)
+!(exam.subjects.contains(\"Latin\") && start.weekday == 1)";
+
+const SCRIPT_HELP: &str = "\
+exam: id, duration (minutes), subjects, tags, required_tags, examiners (shorthands), examinees (count)
+room: number, tags
+start: year, month, day, weekday (1 = monday), hour, minute";
+
 impl Settings {
     fn new() -> Self {
         Self {
             visible: false,
+            script_editor: None,
         }
     }
 
-    fn ui(&mut self, ctx: &egui::Context) {
+    fn ui(&mut self, ctx: &egui::Context, data: &mut PlanerData) {
         egui::Window::new("settings")
             .open(&mut self.visible)
             .collapsible(false)
@@ -1245,7 +1880,153 @@ impl Settings {
                 egui::widgets::global_dark_light_mode_buttons(ui);
             });
 
+            egui::ComboBox::from_label("timezone")
+                .selected_text(data.timezone.name())
+            .show_ui(ui, |ui| {
+                for zone in TZ_VARIANTS {
+                    ui.selectable_value(&mut data.timezone, zone, zone.name());
+                }
+            })
+            .response.on_hover_text_at_pointer("the times of the plan are in this timezone, imported calendars are converted to it");
+
+            ui.separator();
+            Self::rules_ui(ui, data, &mut self.script_editor);
+        });
+
+        self.script_editor_ui(ctx, data);
+    }
+
+    /// the constraints of the plan, they are saved with it
+    fn rules_ui(ui: &mut egui::Ui, data: &mut PlanerData, script_editor: &mut Option<ScriptEditor>) {
+        let mut changed = false;
+        let mut remove = None;
+
+        egui::CollapsingHeader::new("constraints").default_open(true).show(ui, |ui| {
+            egui::Grid::new("rules_grid").num_columns(3).striped(true).show(ui, |ui| {
+                for (i, rule) in data.rules.rules.iter_mut().enumerate() {
+                    changed |= ui.checkbox(&mut rule.enabled, rule.kind.name()).changed();
+
+                    ui.add_enabled_ui(rule.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            match &mut rule.kind {
+                                RuleKind::StudentExamsPerDay { max } => {
+                                    changed |= ui.add(egui::DragValue::new(max).clamp_range(1..=10).suffix(" exams")).changed();
+                                },
+                                RuleKind::MatchingTags { required, optional } => {
+                                    changed |= ui.add(egui::DragValue::new(required).clamp_range(0..=10).prefix("required ")).changed();
+                                    changed |= ui.add(egui::DragValue::new(optional).clamp_range(0..=10).prefix("optional ")).changed();
+                                },
+                                RuleKind::Script { .. } => {
+                                    if ui.button("edit").clicked() {
+                                        *script_editor = Some(ScriptEditor { rule: i, test: None });
+                                    }
+                                    if ui.button("remove").clicked() {
+                                        remove = Some(i);
+                                    }
+                                },
+                                _ => (),
+                            }
+                        });
+                    });
+
+                    if rule.kind.is_hard() {
+                        ui.add_enabled_ui(rule.enabled, |ui| {
+                            egui::ComboBox::from_id_source(("rule_severity", i))
+                                .selected_text(rule.severity.name())
+                            .show_ui(ui, |ui| {
+                                for severity in Severity::ALL {
+                                    changed |= ui.selectable_value(&mut rule.severity, severity, severity.name()).changed();
+                                }
+                            }).response.on_hover_text_at_pointer("exams are never placed where an error is raised, warnings and infos are only shown");
+                        });
+                    } else {
+                        changed |= ui.add_enabled(rule.enabled, egui::Slider::new(&mut rule.weight, 0..=10).text("weight")).changed();
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("add script").on_hover_text_at_pointer("write a custom constraint").clicked() {
+                    let name = format!("script {}", data.rules.rules.len() + 1);
+                    data.rules.rules.push(Rule::new(RuleKind::Script { name, hard: true, source: SCRIPT_TEMPLATE.to_owned() }));
+                    *script_editor = Some(ScriptEditor { rule: data.rules.rules.len() - 1, test: None });
+                    changed = true;
+                }
+
+                if ui.button("reset").on_hover_text_at_pointer("use the built-in constraints").clicked() {
+                    data.rules = RuleSet::default();
+                    *script_editor = None;
+                    changed = true;
+                }
+            });
         });
+
+        if let Some(i) = remove {
+            data.rules.rules.remove(i);
+            *script_editor = None;
+            changed = true;
+        }
+
+        if changed {
+            data.apply_rules();
+        }
+    }
+
+    fn script_editor_ui(&mut self, ctx: &egui::Context, data: &mut PlanerData) {
+        let editor = match &mut self.script_editor {
+            Some(v) => v,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut changed = false;
+        let mut run_test = None;
+
+        egui::Window::new("script").open(&mut open).resizable(true).show(ctx, |ui| {
+            let (name, hard, source) = match data.rules.rules.get_mut(editor.rule).map(|v| &mut v.kind) {
+                Some(RuleKind::Script { name, hard, source }) => (name, hard, source),
+                _ => return,
+            };
+
+            ui.horizontal(|ui| {
+                changed |= ui.text_edit_singleline(name).changed();
+                changed |= ui.checkbox(hard, "hard").on_hover_text_at_pointer("hard constraints return a bool or a reason, soft ones a score").changed();
+            });
+            ui.label(egui::RichText::new(SCRIPT_HELP).small());
+
+            changed |= ui.add(egui::TextEdit::multiline(source)
+                .code_editor()
+                .desired_rows(12)
+                .desired_width(f32::INFINITY)
+            ).changed();
+
+            match CompiledScript::compile(source) {
+                Ok(script) => {
+                    if ui.button("test").on_hover_text_at_pointer("run the script on all booked exams").clicked() {
+                        run_test = Some((script, *hard));
+                    }
+                },
+                Err(err) => { ui.label(egui::RichText::new(err).color(egui::Color32::RED)); },
+            }
+
+            match &editor.test {
+                Some(Ok(res)) => { ui.label(res.as_str()); },
+                Some(Err(err)) => { ui.label(egui::RichText::new(err).color(egui::Color32::RED)); },
+                None => (),
+            }
+        });
+
+        if changed {
+            editor.test = None;
+            data.apply_rules();
+        }
+        if let Some((script, hard)) = run_test {
+            editor.test = Some(script.test(hard, data));
+        }
+        if !open {
+            self.script_editor = None;
+        }
     }
 }
 
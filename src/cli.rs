@@ -0,0 +1,235 @@
+use chrono::prelude::*;
+
+use planer_core::{PlanerData, planer::violation::Severity, export::{html::HtmlOptions, pdf::PdfLayout, sheet::{SheetFormat, CsvDialect}}};
+
+pub const USAGE: &str = "\
+usage: planer <command> <plan> [options]
+
+commands:
+    check               compute the conflicts of the plan
+    solve               place all unfinished exams, then check
+
+options:
+    --day <yyyy-mm-dd>  the day the solver places exams on (default: today)
+    --incremental       also move unpinned booked exams to fix conflicts, as few as possible
+    --seed <number>     break ties between equally good placements with this seed
+                        (default: the seed saved in the plan)
+    --save              write the solved plan back to <plan>
+    --out <file>        write the solved plan to <file>
+    --ics <dir>         export teacher, student and room calendars
+    --html <dir>        export the html pages
+    --pdf <layout> <file>
+                        export a pdf (layouts: room-grid, teachers, students, days)
+    --csv <file>        export the bookings as csv
+    --excel-csv <file>  export the bookings as csv with semicolons
+    --xlsx <file>       export the bookings as xlsx
+    --quiet             only print problems
+
+exit codes: 0 no conflicts, 1 conflicts or unplaced exams, 2 invalid arguments or io errors
+";
+
+enum Export {
+    Ics(String),
+    Html(String),
+    Pdf(PdfLayout, String),
+    Sheet(SheetFormat, String),
+}
+
+struct Args {
+    solve: bool,
+    incremental: bool,
+    seed: Option<u64>,
+    plan: String,
+    day: Option<Date<Utc>>,
+    save: Option<String>,
+    exports: Vec<Export>,
+    quiet: bool,
+}
+
+fn parse(args: Vec<String>) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let solve = match args.next().as_deref() {
+        Some("check") => false,
+        Some("solve") => true,
+        Some(other) => return Err(format!("unknown command {other:?}")),
+        None => return Err("missing command".to_owned()),
+    };
+    let plan = args.next().ok_or("missing plan file")?;
+
+    let mut res = Args { solve, incremental: false, seed: None, plan, day: None, save: None, exports: Vec::new(), quiet: false };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {arg}"));
+        match &arg[..] {
+            "--day" => {
+                let day = value()?;
+                let day = NaiveDate::parse_from_str(&day, "%Y-%m-%d").map_err(|err| format!("invalid day {day:?}: {err}"))?;
+                res.day = Some(Date::from_utc(day, Utc));
+            },
+            "--incremental" => res.incremental = true,
+            "--seed" => {
+                let seed = value()?;
+                res.seed = Some(seed.parse().map_err(|err| format!("invalid seed {seed:?}: {err}"))?);
+            },
+            "--save" => res.save = Some(res.plan.clone()),
+            "--out" => res.save = Some(value()?),
+            "--ics" => res.exports.push(Export::Ics(value()?)),
+            "--html" => res.exports.push(Export::Html(value()?)),
+            "--pdf" => {
+                let layout = match &value()?[..] {
+                    "room-grid" => PdfLayout::RoomGrid,
+                    "teachers" => PdfLayout::Teachers,
+                    "students" => PdfLayout::Students,
+                    "days" => PdfLayout::Days,
+                    other => return Err(format!("unknown pdf layout {other:?}")),
+                };
+                res.exports.push(Export::Pdf(layout, value()?));
+            },
+            "--csv" => res.exports.push(Export::Sheet(SheetFormat::Csv(CsvDialect::COMMA), value()?)),
+            "--excel-csv" => res.exports.push(Export::Sheet(SheetFormat::Csv(CsvDialect::EXCEL), value()?)),
+            "--xlsx" => res.exports.push(Export::Sheet(SheetFormat::Xlsx, value()?)),
+            "--quiet" => res.quiet = true,
+            _ => return Err(format!("unknown option {arg:?}")),
+        }
+    }
+
+    if res.save.is_some() && !res.solve { return Err("--save and --out only make sense with solve".to_owned()) }
+    if res.incremental && !res.solve { return Err("--incremental only makes sense with solve".to_owned()) }
+    if res.seed.is_some() && !res.solve { return Err("--seed only makes sense with solve".to_owned()) }
+
+    Ok(res)
+}
+
+/// runs the command line interface and returns the exit code
+pub fn run(args: Vec<String>) -> i32 {
+    if args.iter().any(|v| v == "--help" || v == "-h") {
+        print!("{USAGE}");
+        return 0;
+    }
+
+    let args = match parse(args) {
+        Ok(v) => v,
+        Err(err) => {
+            eprint!("error: {err}\n\n{USAGE}");
+            return 2;
+        },
+    };
+
+    match execute(&args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("error: {err}");
+            2
+        },
+    }
+}
+
+/// returns whether the plan is free of problems
+fn execute(args: &Args) -> Result<bool, String> {
+    let mut data = PlanerData::try_load(&args.plan)?;
+    if let Some(seed) = args.seed {
+        data.solver.seed = seed;
+    }
+
+    let day = args.day.unwrap_or_else(Utc::today);
+    // the number of exams the incremental solver moved
+    let moved = if args.solve && args.incremental {
+        let (Ok(res) | Err(res)) = data.solve_incremental_on(day);
+        Some(res.moved_exams.len())
+    } else {
+        if args.solve {
+            let _ = data.solve_on(day);
+        } else {
+            data.compute_conflicts();
+        }
+        None
+    };
+
+    if let Some(path) = &args.save {
+        data.save_to(path)?;
+    }
+
+    for export in &args.exports {
+        match export {
+            Export::Ics(dir) => { data.export_ics_dir(dir).map_err(|err| format!("could not write calendars: {err}"))?; },
+            Export::Html(dir) => { data.export_html(dir, &HtmlOptions::default()).map_err(|err| format!("could not write html pages: {err}"))?; },
+            Export::Pdf(layout, path) => data.export_pdf(*layout, path)?,
+            Export::Sheet(format, path) => data.export_sheet(*format, path)?,
+        }
+    }
+
+    let violations = |severity| -> Vec<_> {
+        data.finished_exams.iter()
+            .flat_map(|exam| {
+                let id = if exam.id.is_empty() { "[unnamed]".to_owned() } else { exam.id.clone() };
+                exam.violations.iter()
+                    .filter(move |v| v.severity == severity && v.is_open())
+                    .map(move |v| (id.clone(), v.message.clone()))
+            })
+        .collect()
+    };
+    let conflicts = violations(Severity::Error);
+    let warnings = violations(Severity::Warning);
+
+    if !args.quiet {
+        println!("plan:       {}", data.plan_name());
+        println!("students:   {}", data.students.len());
+        println!("teachers:   {}", data.teachers.len());
+        println!("rooms:      {}", data.rooms.len());
+        println!("booked:     {}", data.finished_exams.len());
+        println!("unbooked:   {}", data.unfinished_exams.len());
+        if let Some(moved) = moved {
+            println!("moved:      {moved}");
+        }
+        println!("conflicts:  {}", conflicts.len());
+        println!("warnings:   {}", warnings.len());
+    }
+
+    for (id, err) in &conflicts {
+        println!("conflict in exam {id}: {err}");
+    }
+    // warnings do not change the exit code, --quiet hides them
+    if !args.quiet {
+        for (id, warning) in &warnings {
+            println!("warning in exam {id}: {warning}");
+        }
+    }
+
+    // unbooked exams are only a problem if the solver was asked to place them
+    let unplaced = args.solve && !data.unfinished_exams.is_empty();
+    if unplaced {
+        println!("{} exams could not be placed", data.unfinished_exams.len());
+    }
+
+    Ok(conflicts.is_empty() && !unplaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: &str) -> Vec<String> {
+        v.split(' ').map(|v| v.to_owned()).collect()
+    }
+
+    #[test]
+    fn check_exit_codes() {
+        let path = std::env::temp_dir().join(format!("planer-cli-{}.plan", uuid::Uuid::new_v4()));
+        let mut data = PlanerData::default();
+        data.add_room("A1.03".to_owned(), Vec::new());
+        data.save_to(&path).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(run(args(&format!("check {path} --quiet"))), 0);
+        assert_eq!(run(args(&format!("solve {path} --day 2022-12-15 --out {path}"))), 0);
+        assert_eq!(run(args(&format!("check {path} --save"))), 2, "only solve writes the plan");
+        assert_eq!(run(args(&format!("solve {path} --incremental --quiet"))), 0);
+        assert_eq!(run(args(&format!("solve {path} --seed 42 --save --quiet"))), 0);
+        assert_eq!(PlanerData::load(path).solver.seed, 42, "the seed should be saved with the plan");
+        assert_eq!(run(args(&format!("check {path} --seed 42"))), 2);
+        assert_eq!(run(args(&format!("check {path} --pdf week {path}.pdf"))), 2);
+        assert_eq!(run(args("check /does/not/exist.plan")), 2);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use eframe::egui;
+
+use planer_core::planer::{PlanerData, Exam, store::{Id, AsUuid}, violation::{Entity, Severity}};
+
+const ERROR_ICON: &str      = "⛔";
+const WARNING_ICON: &str    = "⚠";
+const INFO_ICON: &str       = "ℹ";
+const CLOSE_ICON: &str      = "🗙";
+
+/// the icon and color violations of a severity are shown with
+pub fn severity_icon(severity: Severity) -> (&'static str, egui::Color32) {
+    match severity {
+        Severity::Error => (ERROR_ICON, egui::Color32::RED),
+        Severity::Warning => (WARNING_ICON, egui::Color32::YELLOW),
+        Severity::Info => (INFO_ICON, egui::Color32::LIGHT_BLUE),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dock {
+    Left,
+    Right,
+    Floating,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Grouping {
+    Rule,
+    Person,
+    Day,
+}
+
+/// a violation of a booked exam as it is listed in the panel
+struct Entry {
+    exam: Id<Exam>,
+    name: String,
+    start: DateTime<Utc>,
+    severity: Severity,
+    rule: String,
+    message: String,
+    acknowledged: bool,
+    people: Vec<String>,
+}
+
+/// lists the violations of all booked exams
+pub struct ConflictPanel {
+    pub visible: bool,
+    dock: Dock,
+    grouping: Grouping,
+    hide_acknowledged: bool,
+}
+
+impl ConflictPanel {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            dock: Dock::Left,
+            grouping: Grouping::Rule,
+            hide_acknowledged: true,
+        }
+    }
+
+    /// shows the panel if it is visible, returns the exam of the entry that was clicked
+    pub fn show(&mut self, ctx: &egui::Context, data: &PlanerData) -> Option<Id<Exam>> {
+        if !self.visible { return None }
+
+        let mut clicked = None;
+        match self.dock {
+            Dock::Left => {
+                egui::SidePanel::left("conflict_panel").resizable(true).min_width(250.0).show(ctx, |ui| clicked = self.ui(ui, data));
+            },
+            Dock::Right => {
+                egui::SidePanel::right("conflict_panel").resizable(true).min_width(250.0).show(ctx, |ui| clicked = self.ui(ui, data));
+            },
+            Dock::Floating => {
+                let mut open = true;
+                egui::Window::new("conflicts").open(&mut open).resizable(true).show(ctx, |ui| clicked = self.ui(ui, data));
+                if !open { self.visible = false }
+            },
+        }
+
+        clicked
+    }
+
+    fn entries(&self, data: &PlanerData) -> Vec<Entry> {
+        let hide_acknowledged = self.hide_acknowledged;
+        data.finished_exams.iter()
+            .filter_map(|exam| Some((exam, exam.pairing?.1)))
+            .flat_map(|(exam, start)| {
+                exam.violations.iter()
+                    .filter(move |v| !(hide_acknowledged && v.acknowledged))
+                    .map(move |v| Entry {
+                        exam: exam.id(),
+                        name: if exam.id.is_empty() { "[unnamed]".to_owned() } else { exam.id.clone() },
+                        start,
+                        severity: v.severity,
+                        rule: v.rule.clone(),
+                        message: v.message.clone(),
+                        acknowledged: v.acknowledged,
+                        people: v.involved.iter()
+                            .filter_map(|entity| match entity {
+                                Entity::Teacher(id) => data.teachers.get(*id).map(|v| v.name.to_string()),
+                                Entity::Student(id) => data.students.get(*id).map(|v| v.name.to_string()),
+                                _ => None,
+                            })
+                        .collect(),
+                    })
+            })
+        .collect()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, data: &PlanerData) -> Option<Id<Exam>> {
+        ui.horizontal(|ui| {
+            ui.heading("conflicts");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button(CLOSE_ICON).on_hover_text_at_pointer("close").clicked() { self.visible = false }
+
+                egui::ComboBox::from_id_source("conflict_dock")
+                    .selected_text(match self.dock { Dock::Left => "left", Dock::Right => "right", Dock::Floating => "window" })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.dock, Dock::Left, "left");
+                    ui.selectable_value(&mut self.dock, Dock::Right, "right");
+                    ui.selectable_value(&mut self.dock, Dock::Floating, "window");
+                });
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("group by");
+            ui.selectable_value(&mut self.grouping, Grouping::Rule, "type");
+            ui.selectable_value(&mut self.grouping, Grouping::Person, "person");
+            ui.selectable_value(&mut self.grouping, Grouping::Day, "day");
+        });
+        ui.checkbox(&mut self.hide_acknowledged, "hide acknowledged");
+        ui.separator();
+
+        let entries = self.entries(data);
+        if entries.is_empty() {
+            ui.weak("no conflicts");
+            return None;
+        }
+
+        // an entry is listed once for every person involved
+        let mut groups: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+        for entry in &entries {
+            let keys = match self.grouping {
+                Grouping::Rule => vec![entry.rule.clone()],
+                Grouping::Day => vec![entry.start.format("%Y-%m-%d").to_string()],
+                Grouping::Person if entry.people.is_empty() => vec!["no person".to_owned()],
+                Grouping::Person => entry.people.clone(),
+            };
+            for key in keys {
+                groups.entry(key).or_default().push(entry);
+            }
+        }
+
+        let mut clicked = None;
+        egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+            for (group, entries) in groups {
+                egui::CollapsingHeader::new(format!("{group} ({})", entries.len()))
+                    .id_source(("conflict_group", &group))
+                    .default_open(true)
+                .show(ui, |ui| {
+                    for entry in entries {
+                        let (icon, color) = severity_icon(entry.severity);
+                        let summary = entry.message.lines().next().unwrap_or_default();
+                        let mut text = egui::RichText::new(format!("{icon} {} {}: {summary}", entry.start.format("%d.%m. %H:%M"), entry.name)).color(color);
+                        if entry.acknowledged { text = text.weak() }
+
+                        if ui.selectable_label(false, text)
+                            .on_hover_text_at_pointer(format!("{}: {}\n\nclick to show in the calendar", entry.rule, entry.message))
+                        .clicked() {
+                            clicked = Some(entry.exam);
+                        }
+                    }
+                });
+            }
+        });
+
+        clicked
+    }
+}
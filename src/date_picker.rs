@@ -0,0 +1,68 @@
+use chrono::{prelude::*, Duration};
+use eframe::egui;
+
+const CALENDAR_ICON: &str   = "📅";
+const WEEKDAYS: [&str; 7]   = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+/// the month that is shown while the picker is open
+#[derive(Clone, Copy)]
+struct ShownMonth(Date<Utc>);
+
+/// a button showing `date` that opens a month calendar to pick another one, returns whether a day was picked
+pub fn date_picker(ui: &mut egui::Ui, id: egui::Id, date: &mut Date<Utc>) -> bool {
+    let button = ui.button(format!("{CALENDAR_ICON} {}", date.format("%a %d.%m.%Y")));
+
+    let mut shown = ui.memory().data.get_temp::<ShownMonth>(id).map(|v| v.0);
+    if button.clicked() {
+        shown = match shown {
+            Some(_) => None,
+            None => Some(first_of_month(*date)),
+        };
+    }
+
+    let mut picked = false;
+    if let Some(month) = shown.as_mut() {
+        let area = egui::Area::new(id)
+            .order(egui::Order::Foreground)
+            .fixed_pos(button.rect.left_bottom())
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.small_button("◀").clicked() { *month = first_of_month(month.pred()) }
+                    ui.strong(month.format("%B %Y").to_string());
+                    if ui.small_button("▶").clicked() { *month = first_of_month(*month + Duration::days(31)) }
+                });
+
+                egui::Grid::new(id.with("days")).show(ui, |ui| {
+                    for name in WEEKDAYS { ui.weak(name); }
+                    ui.end_row();
+
+                    for _ in 0..month.weekday().num_days_from_monday() { ui.label(""); }
+
+                    let mut day = *month;
+                    while day.month() == month.month() {
+                        if ui.selectable_label(day == *date, day.day().to_string()).clicked() {
+                            *date = day;
+                            picked = true;
+                        }
+                        if day.weekday() == Weekday::Sun { ui.end_row() }
+                        day = day.succ();
+                    }
+                });
+            });
+        }).response;
+
+        if picked || (area.clicked_elsewhere() && !button.clicked()) { shown = None }
+    }
+
+    match shown {
+        Some(month) => ui.memory().data.insert_temp(id, ShownMonth(month)),
+        None => { ui.memory().data.remove::<ShownMonth>(id); },
+    }
+
+    picked
+}
+
+fn first_of_month(date: Date<Utc>) -> Date<Utc> {
+    Utc.ymd(date.year(), date.month(), 1)
+}
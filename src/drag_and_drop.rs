@@ -90,11 +90,35 @@ where
     }
 }
 
+/// the data of the current drag, if it is a `T`
+pub fn dragged<T>(ui: &egui::Ui) -> Option<T>
+where
+    T: 'static + std::any::Any + Clone + Send + Sync,
+{
+    let mut mem = ui.memory();
+    if !mem.is_anything_being_dragged() || mem.data.get_temp::<DraggingActive>(egui::Id::null()).is_none() { return None }
+
+    mem.data.get_temp::<DraggingData<T>>(egui::Id::null()).map(|v| v.data)
+}
+
 pub fn drop_target<T, R>(
     ui: &mut egui::Ui,
     body: impl FnOnce(&mut egui::Ui) -> R,
     on_drop: impl FnOnce(T),
 ) -> egui::InnerResponse<R>
+where
+    T: 'static + std::any::Any + Clone + Send + Sync,
+{
+    highlighted_drop_target(ui, false, body, on_drop)
+}
+
+/// a drop target that is filled with the selection color while something is dragged and `highlight` is set
+pub fn highlighted_drop_target<T, R>(
+    ui: &mut egui::Ui,
+    highlight: bool,
+    body: impl FnOnce(&mut egui::Ui) -> R,
+    on_drop: impl FnOnce(T),
+) -> egui::InnerResponse<R>
 where
     T: 'static + std::any::Any + Clone + Send + Sync,
 {
@@ -163,6 +187,10 @@ where
 
     let mut fill = style.bg_fill;
     let mut stroke = style.bg_stroke;
+    if is_being_dragged && can_accept_drag && highlight {
+        fill = ui.visuals().selection.bg_fill;
+        stroke = ui.visuals().selection.stroke;
+    }
     if is_being_dragged && !can_accept_drag {
         let window_fill = ui.visuals().window_fill();
         fill = egui::ecolor::tint_color_towards(fill, window_fill);
@@ -1,13 +1,21 @@
 use crate::app::PlanerApp;
 
 mod app;
+mod cli;
+mod conflicts;
+mod date_picker;
 mod drag_and_drop;
-mod planer;
 mod modal;
+mod schedule;
 mod search;
-mod solver;
 
 fn main() {
+    // any argument switches to the command line interface
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
+
     let native_options = eframe::NativeOptions {
         decorated: true,
         resizable: true,
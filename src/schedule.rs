@@ -0,0 +1,321 @@
+use chrono::{prelude::*, Duration};
+use eframe::{egui, epaint::vec2};
+
+use planer_core::planer::{PlanerData, Exam, Teacher, Student, store::{Id, AsUuid}, calendar::TimeRange, agenda::AgendaEntry};
+
+use crate::drag_and_drop::{drag_source, dragged, highlighted_drop_target};
+
+const WARNING_ICON: &str = "⚠";
+
+/// what a teacher does in an exam, by the examiner slot they are in
+const ROLES: [&str; 3] = ["first examiner", "second examiner", "third examiner"];
+
+#[derive(Debug, Clone)]
+struct DraggingBooking(Id<Exam>);
+
+/// every day from the first to the last booked exam, or only today if nothing is booked yet
+pub fn exam_period(data: &PlanerData) -> Vec<Date<Utc>> {
+    let days: Vec<_> = data.finished_exams.iter().filter_map(|exam| Some(exam.pairing?.1.date())).collect();
+    match (days.iter().min(), days.iter().max()) {
+        (Some(&first), Some(&last)) => (0..=(last - first).num_days()).map(|i| first + Duration::days(i)).collect(),
+        _ => vec![Utc::today()],
+    }
+}
+
+/// the calendars of the selected teachers side by side, one grid for every day of the exam period
+pub struct TeacherSchedule {
+    selected: Vec<Id<Teacher>>,
+}
+
+impl TeacherSchedule {
+    pub fn new() -> Self {
+        Self { selected: Vec::new() }
+    }
+
+    /// returns the error if an exam could not be moved
+    pub fn show(&mut self, ctx: &egui::Context, data: &mut PlanerData) -> Option<String> {
+        egui::SidePanel::left("teacher_select_panel").resizable(true).min_width(200.0).show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.heading("teachers");
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                for teacher in data.teachers.iter() {
+                    let id = teacher.id();
+                    let mut selected = self.selected.contains(&id);
+                    if ui.checkbox(&mut selected, format!("{} ({})", teacher.name, teacher.shorthand)).changed() {
+                        if selected { self.selected.push(id) } else { self.selected.retain(|v| *v != id) }
+                    }
+                }
+            });
+        });
+        self.selected.retain(|v| data.teachers.contains(*v));
+
+        let mut error = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.selected.is_empty() {
+                ui.weak("select one or more teachers to see their exams");
+                return;
+            }
+            if data.timetable.times.is_empty() { return }
+
+            let moves = egui::ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
+                let mut moves = Vec::new();
+                for day in exam_period(data) {
+                    ui.heading(day.format("%A %d.%m.%Y").to_string());
+                    self.day_ui(ui, data, day, &mut moves);
+                    ui.add_space(10.0);
+                }
+                moves
+            }).inner;
+
+            for (exam, start) in moves {
+                match data.move_exam(exam, start) {
+                    Ok(()) => data.schedule_recompute(),
+                    Err(err) => error = Some(err),
+                }
+            }
+        });
+
+        error
+    }
+
+    /// the exams dropped on a lesson are added to `moves` with their new start
+    fn day_ui(&self, ui: &mut egui::Ui, data: &PlanerData, day: Date<Utc>, moves: &mut Vec<(Id<Exam>, DateTime<Utc>)>) {
+        let time_width = 50.0;
+        let column_width = 200.0;
+        let header_height = 25.0;
+        let padding = 5.0;
+        let minute_height = 2.0;
+
+        let start_t = data.timetable.times[0].start;
+        let last_lesson = data.timetable.times.last().unwrap();
+        let total_time = (last_lesson.start + last_lesson.duration).signed_duration_since(start_t).num_minutes() as f32;
+        let day_start = day.and_time(start_t).unwrap();
+
+        let (grid, _) = ui.allocate_exact_size(
+            vec2(time_width + (column_width + padding) * self.selected.len() as f32, total_time * minute_height + header_height),
+            egui::Sense::hover(),
+        );
+        let column = |i: usize| grid.left() + time_width + padding + (column_width + padding) * i as f32;
+        let rect_of = |x: f32, width: f32, start: DateTime<Utc>, duration: Duration| {
+            let y = grid.top() + header_height + (start - day_start).num_minutes() as f32 * minute_height;
+            egui::Rect::from_min_size(egui::pos2(x, y), vec2(width, duration.num_minutes() as f32 * minute_height)).intersect(grid)
+        };
+
+        // the free lessons in the room of the dragged exam are highlighted
+        let dragged = dragged::<DraggingBooking>(ui).and_then(|v| {
+            let exam = data.exam(v.0)?;
+            Some((v.0, exam.duration, exam.pairing?.0))
+        });
+        let slots = match dragged {
+            Some((exam, ..)) => data.find_slots(exam, TimeRange::day(day)),
+            None => Vec::new(),
+        };
+
+        for (i, teacher) in self.selected.iter().filter_map(|v| data.teachers.get(*v)).enumerate() {
+            let rect = egui::Rect::from_min_size(egui::pos2(column(i), grid.top()), vec2(column_width, header_height));
+            ui.child_ui(rect, egui::Layout::top_down(egui::Align::Center)).strong(format!("{} ({})", teacher.name, teacher.shorthand));
+        }
+
+        for lesson in &data.timetable.times {
+            let lesson_start = day.and_time(lesson.start).unwrap();
+            let rect = rect_of(grid.left(), time_width, lesson_start, lesson.duration);
+            ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP)).group(|ui| {
+                ui.label(lesson.start.format("%H:%M").to_string());
+                ui.allocate_space(ui.available_size());
+            });
+
+            let fits = dragged.map_or(false, |(_, duration, room)| {
+                slots.iter().any(|v| v.room == room && v.fits(lesson_start, duration))
+            });
+
+            for i in 0..self.selected.len() {
+                let mut ui = ui.child_ui(rect_of(column(i), column_width, lesson_start, lesson.duration), egui::Layout::top_down(egui::Align::TOP));
+                highlighted_drop_target(&mut ui, fits, |ui| {
+                    ui.allocate_space(ui.available_size());
+                }, |v: DraggingBooking| {
+                    moves.push((v.0, lesson_start));
+                });
+            }
+        }
+
+        let whole_day = day.and_hms(0, 0, 0);
+        for (i, teacher) in self.selected.iter().filter_map(|v| data.teachers.get(*v)).enumerate() {
+            for blocked in teacher.blocked.get_booked_from_to(&whole_day, Duration::days(1)) {
+                let rect = rect_of(column(i), column_width, blocked.start, blocked.duration);
+                if !rect.is_positive() { continue }
+                ui.painter().rect_filled(rect, 2.0, ui.visuals().faint_bg_color);
+                ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP)).weak("blocked")
+                    .on_hover_text_at_pointer(blocked.data.summary.as_str());
+            }
+
+            for booking in teacher.calendar.get_booked_from_to(&whole_day, Duration::days(1)) {
+                let rect = rect_of(column(i), column_width, booking.start, booking.duration);
+                let exam = match data.exam(booking.data) { Some(v) => v, None => continue };
+                if !rect.is_positive() { continue }
+                let room = exam.pairing.and_then(|(room, _)| data.rooms.get(room)).map_or("<invalid>", |v| &v.number[..]);
+                let role = exam.examiners.iter().position(|v| *v == Some(teacher.id())).map_or("examiner", |v| ROLES[v]);
+
+                let card = |ui: &mut egui::Ui| {
+                    egui::Frame::group(ui.style()).fill(ui.visuals().extreme_bg_color).show(ui, |ui| {
+                        ui.set_min_size(ui.available_size());
+                        ui.strong(format!("{} · {room}", exam.id));
+                        ui.weak(format!("{}-{} · {role}", booking.start.format("%H:%M"), booking.end().format("%H:%M")));
+                    }).response
+                };
+
+                let mut ui = ui.child_ui(rect, egui::Layout::top_down(egui::Align::TOP));
+                if exam.pinned {
+                    card(&mut ui).on_hover_text_at_pointer("pinned exams can not be moved");
+                } else {
+                    let (id, booking) = (ui.id().with(("teacher_booking_drag", i, booking.data.uuid())), booking.data);
+                    drag_source(&mut ui, id, card, || DraggingBooking(booking), || {});
+                }
+            }
+        }
+    }
+}
+
+/// the agenda of one student next to a list of all students that flags the ones whose plan needs attention
+pub struct StudentSchedule {
+    selected: Option<Id<Student>>,
+    search: String,
+    max_per_day: usize,
+    only_flagged: bool,
+}
+
+impl StudentSchedule {
+    pub fn new() -> Self {
+        Self {
+            selected: None,
+            search: String::new(),
+            max_per_day: 2,
+            only_flagged: false,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, data: &PlanerData) {
+        egui::SidePanel::left("student_list_panel").resizable(true).min_width(300.0).show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.heading("students");
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("search"));
+            ui.add(egui::DragValue::new(&mut self.max_per_day).clamp_range(1..=10).prefix("flag more than ").suffix(" exams per day"));
+            ui.checkbox(&mut self.only_flagged, "only flagged");
+            ui.separator();
+
+            let search = self.search.to_uppercase();
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                egui::Grid::new("student_summaries").striped(true).num_columns(4).show(ui, |ui| {
+                    ui.strong("name");
+                    ui.strong("exams");
+                    ui.strong("most per day");
+                    ui.strong("no room");
+                    ui.end_row();
+
+                    for summary in data.student_summaries() {
+                        let name = match data.students.get(summary.student) { Some(v) => v.name.to_string(), None => continue };
+                        let flagged = summary.is_flagged(self.max_per_day);
+                        if !name.to_uppercase().contains(&search) || (self.only_flagged && !flagged) { continue }
+
+                        let label = if flagged {
+                            egui::RichText::new(format!("{WARNING_ICON} {name}")).color(egui::Color32::YELLOW)
+                        } else { egui::RichText::new(name) };
+                        if ui.selectable_label(self.selected == Some(summary.student), label).clicked() {
+                            self.selected = Some(summary.student);
+                        }
+
+                        ui.label(summary.exams.to_string());
+                        match summary.busiest_day {
+                            Some((day, n)) => {
+                                let text = format!("{n} on {}", day.format("%d.%m."));
+                                if n > self.max_per_day { ui.colored_label(egui::Color32::YELLOW, text) } else { ui.label(text) }
+                            },
+                            None => ui.weak("-"),
+                        };
+                        if summary.unbooked > 0 {
+                            ui.colored_label(egui::Color32::YELLOW, summary.unbooked.to_string());
+                        } else {
+                            ui.weak("0");
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let student = match self.selected.and_then(|v| data.students.get(v)) {
+                Some(v) => v,
+                None => {
+                    ui.weak("select a student to see their exams");
+                    return;
+                },
+            };
+            let agenda = data.student_agenda(student.id());
+
+            ui.horizontal(|ui| {
+                ui.heading(student.name.to_string());
+                if ui.button("copy").on_hover_text_at_pointer("copy the agenda as text, e.g. to answer an email").clicked() {
+                    ui.output().copied_text = agenda_text(student, &agenda);
+                }
+            });
+            ui.separator();
+
+            if agenda.is_empty() {
+                ui.weak("no exams");
+                return;
+            }
+
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                for entry in &agenda {
+                    ui.group(|ui| {
+                        ui.set_width(ui.available_width());
+                        match entry.booking {
+                            Some(_) => ui.strong(when(entry)),
+                            None => ui.colored_label(egui::Color32::YELLOW, format!("{WARNING_ICON} {}", when(entry))),
+                        };
+                        ui.label(what(entry));
+                        ui.label(format!("examiners: {}", examiners(entry)));
+                        if let Some(arrival) = entry.arrival().filter(|_| entry.exam.preparation > Duration::zero()) {
+                            ui.label(format!("preparation from {} ({}min)", arrival.format("%H:%M"), entry.exam.preparation.num_minutes()));
+                        }
+                    });
+                }
+            });
+        });
+    }
+}
+
+fn when(entry: &AgendaEntry) -> String {
+    match entry.booking {
+        Some((room, start)) => format!("{} {}-{}, room {}", start.format("%A %d.%m.%Y"), start.format("%H:%M"), (start + entry.exam.duration).format("%H:%M"), room.number),
+        None => "no room and time yet".to_owned(),
+    }
+}
+
+fn what(entry: &AgendaEntry) -> String {
+    if entry.exam.subjects.is_empty() {
+        entry.exam.id.clone()
+    } else {
+        format!("{} ({})", entry.exam.id, entry.exam.subjects.join(", "))
+    }
+}
+
+fn examiners(entry: &AgendaEntry) -> String {
+    if entry.examiners.is_empty() { return "-".to_owned() }
+    entry.examiners.iter().map(|v| v.name.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// the agenda as plain text, one exam per line
+fn agenda_text(student: &Student, agenda: &[AgendaEntry]) -> String {
+    let mut text = format!("exams of {}:\n", student.name);
+    for entry in agenda {
+        text.push_str(&format!("- {}: {}, examiners: {}", when(entry), what(entry), examiners(entry)));
+        if let Some(arrival) = entry.arrival().filter(|_| entry.exam.preparation > Duration::zero()) {
+            text.push_str(&format!(", preparation from {}", arrival.format("%H:%M")));
+        }
+        text.push('\n');
+    }
+    text
+}